@@ -0,0 +1,82 @@
+//Bakes every image in the asset directory into the atlases that the game
+//loads at startup, and reports how tightly each was packed. Images go in
+//the atlas the manifest gives them, or the default one if not listed.
+//
+//    cargo run --bin bake
+
+use ants::window::{atlas::*, manifest::*};
+
+fn main() {
+	let manifest = Manifest::load(MANIFEST).unwrap_or_else(|err| {
+		println!("{}, baking a single atlas.", err);
+		Manifest { textures: vec![] }
+	});
+
+	//atlas of each file, by file name
+	let placement: std::collections::HashMap<_, _> = manifest
+		.textures
+		.into_iter()
+		.map(|entry| (entry.file_name(), entry.atlas))
+		.collect();
+
+	let mut atlases = vec![(
+		DEFAULT_ATLAS.to_owned(),
+		vec![(CHECKERBOARD.to_owned(), checkerboard())],
+	)];
+
+	let dir = std::fs::read_dir(ASSET_DIR).expect("Unable to read asset directory.");
+	for entry in dir.filter_map(|entry| entry.ok()) {
+		let path = entry.path();
+		if path.extension().is_none_or(|ext| ext != "png") {
+			continue;
+		}
+
+		let file = entry.file_name().to_string_lossy().into_owned();
+		let image = match image::open(&path) {
+			Ok(img) => img.into_rgba8(),
+			Err(err) => {
+				println!("Skipping {}: {}", file, err);
+				continue;
+			}
+		};
+
+		let name = placement
+			.get(&file)
+			.map_or(DEFAULT_ATLAS, |name| name.as_str());
+		match atlases.iter_mut().find(|(atlas, _)| atlas == name) {
+			Some((_, images)) => images.push((file, image)),
+			None => atlases.push((
+				name.to_owned(),
+				vec![(CHECKERBOARD.to_owned(), checkerboard()), (file, image)],
+			)),
+		}
+	}
+
+	for (name, images) in atlases {
+		bake(&name, images);
+	}
+}
+
+fn bake(name: &str, images: Vec<(String, image::RgbaImage)>) {
+	let count = images.len();
+	let start = std::time::Instant::now();
+	let atlas = Atlas::pack(images);
+	let (width, height) = atlas.image.dimensions();
+
+	println!(
+		"Packed {} images into {} at {}x{} in {:.2}s, {:.1}% of the atlas used.",
+		count,
+		name,
+		width,
+		height,
+		start.elapsed().as_secs_f32(),
+		100. * atlas.efficiency()
+	);
+
+	let (image_path, meta_path) = Atlas::baked_paths(name);
+	atlas
+		.save(&image_path, &meta_path)
+		.expect("Unable to save baked atlas.");
+
+	println!("Saved {} and {}.", image_path, meta_path);
+}
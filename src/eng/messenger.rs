@@ -3,7 +3,7 @@ use std::time::*;
 use strum::*;
 use strum_macros::*;
 
-use super::{Grid, Griddable};
+use super::{Grid, Griddable, SpatialIndex};
 
 #[derive(Clone, Copy, EnumDiscriminants, EnumCount)]
 #[strum_discriminants(name(MessageTypes))]
@@ -94,6 +94,12 @@ impl Messenger {
 	}
 }
 
+impl Default for Messenger {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl Dispatch {
 	pub fn new(pos: Option<(f32, f32)>, signal: Signal, delay: f32) -> Self {
 		Self { pos, signal, delay }
@@ -1,14 +1,16 @@
 mod messenger;
 mod play;
+mod scheduler;
 mod state;
 pub mod utils;
 
 pub use messenger::*;
 pub use play::*;
+pub use scheduler::*;
 pub use std::sync::mpsc::Sender;
 pub use utils::*;
 
-use crate::window::{External, Instance};
+use crate::window::{External, Instance, Layer, Layers, Static};
 
 pub trait GameObject {
 	type Scene;
@@ -26,9 +28,38 @@ pub trait GameObject {
 		None
 	}
 
-	fn render(&self, external: &External, out: &mut Vec<Instance>) {
+	//Layer the instance from GameObject::instance is drawn on.
+	fn layer(&self) -> Layer {
+		Layer::Entities
+	}
+
+	//Names the instances from GameObject::render_static, which are kept on
+	//the GPU and only rebuilt when the version in the key changes. Suits
+	//what rarely changes and is costly to send every frame, like terrain.
+	fn statics(&self) -> Option<Static> {
+		None
+	}
+
+	//Unclipped, since they're drawn wherever the camera goes.
+	fn render_static(&self, _external: &External, _out: &mut Vec<Instance>) {}
+
+	//Called by the default GameObject::render, so must be called by any
+	//override of it for the static instances to be drawn.
+	fn render_cached(&self, external: &External, out: &mut Layers) {
+		if let Some(key) = self.statics() {
+			out.cached(key, || {
+				let mut instances = vec![];
+				self.render_static(external, &mut instances);
+				instances
+			});
+		}
+	}
+
+	fn render(&self, external: &External, out: &mut Layers) {
+		self.render_cached(external, out);
+
 		if let Some(inst) = self.instance(external) {
-			external.clip(out, inst);
+			external.clip(out, self.layer(), inst);
 		}
 	}
 
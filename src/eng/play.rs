@@ -12,16 +12,32 @@ pub trait Root: GameObject<Scene = ()> + 'static {
 }
 
 pub fn play<World: Root>() -> ! {
+	use tracing_subscriber::{filter::LevelFilter, prelude::*, registry::LookupSpan, Layer};
+
+	//warnings and errors, e.g. from assets failing to load, go to stderr
+	fn log_layer<S>() -> impl Layer<S>
+	where
+		S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+	{
+		tracing_subscriber::fmt::layer()
+			.with_writer(std::io::stderr)
+			.with_filter(LevelFilter::WARN)
+	}
+
 	let mut tracing_guard = if cfg!(feature = "profile") {
 		use tracing_flame::FlameLayer;
-		use tracing_subscriber::prelude::*;
 
 		let (flame_layer, _guard) = FlameLayer::with_file("./tracing.folded").unwrap();
 
-		tracing_subscriber::registry().with(flame_layer).init();
+		tracing_subscriber::registry()
+			.with(flame_layer)
+			.with(log_layer())
+			.init();
 
 		Some(_guard)
 	} else {
+		tracing_subscriber::registry().with(log_layer()).init();
+
 		None
 	};
 
@@ -82,7 +98,12 @@ pub fn play<World: Root>() -> ! {
 					let now = game.api.external.now;
 					let time = now.duration_since(prev).as_secs_f64();
 					if time > FPS_FREQ {
-						println!("fps: {}", (frame_counter as f64 / FPS_FREQ) as i32);
+						let metrics = game.api.external.scheduler.take_metrics();
+						game.set_stats(format!(
+							"fps: {}\n{}",
+							(frame_counter as f64 / FPS_FREQ) as i32,
+							metrics
+						));
 						prev = now;
 						frame_counter = 0;
 						game.cleanup();
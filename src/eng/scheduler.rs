@@ -0,0 +1,164 @@
+//Spreads the planning of relaxed objects over ticks. Every object is
+//given a slot when created, and an object which should plan once every
+//n ticks only does so on the ticks whose bucket, tick % n, matches its
+//own. Objects spawned together are given consecutive slots, so their
+//plans are staggered instead of all landing on the same tick. One is kept
+//in External, so that every object can reach it while updating.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+pub struct Scheduler {
+	tick: AtomicU64,
+	next_slot: AtomicUsize,
+	//plans left this tick
+	budget: AtomicUsize,
+	planned: AtomicUsize,
+	deferred: AtomicUsize,
+	metrics: Mutex<Metrics>,
+}
+
+//Plans run per tick, since the metrics were last taken.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Metrics {
+	pub ticks: usize,
+	pub total: usize,
+	pub max: usize,
+	pub last: usize,
+	//plans which were due, but didn't fit in the budget
+	pub deferred: usize,
+}
+
+impl Scheduler {
+	pub fn new() -> Self {
+		Self {
+			tick: AtomicU64::new(0),
+			next_slot: AtomicUsize::new(0),
+			budget: AtomicUsize::new(usize::MAX),
+			planned: AtomicUsize::new(0),
+			deferred: AtomicUsize::new(0),
+			metrics: Mutex::default(),
+		}
+	}
+
+	//Called once before everything is updated.
+	pub fn begin_tick(&self, budget: usize) {
+		let planned = self.planned.swap(0, Ordering::Relaxed);
+		let deferred = self.deferred.swap(0, Ordering::Relaxed);
+		self.budget.store(budget, Ordering::Relaxed);
+		self.tick.fetch_add(1, Ordering::Relaxed);
+
+		let mut metrics = self.metrics.lock().unwrap();
+		metrics.ticks += 1;
+		metrics.total += planned;
+		metrics.max = metrics.max.max(planned);
+		metrics.last = planned;
+		metrics.deferred += deferred;
+	}
+
+	pub fn tick(&self) -> u64 {
+		self.tick.load(Ordering::Relaxed)
+	}
+
+	pub fn slot(&self) -> usize {
+		self.next_slot.fetch_add(1, Ordering::Relaxed)
+	}
+
+	//Whether `slot` is in the bucket due this tick, for an object which
+	//should plan once every `period` ticks.
+	pub fn due(&self, slot: usize, period: usize) -> bool {
+		let period = period.max(1) as u64;
+		self.tick() % period == slot as u64 % period
+	}
+
+	//Takes a plan out of this tick's budget, returning false if none are
+	//left, in which case the plan should be tried again next tick.
+	pub fn try_plan(&self) -> bool {
+		let taken = self
+			.budget
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |b| b.checked_sub(1))
+			.is_ok();
+
+		if taken {
+			self.planned.fetch_add(1, Ordering::Relaxed);
+		} else {
+			self.deferred.fetch_add(1, Ordering::Relaxed);
+		}
+
+		taken
+	}
+
+	//Returns the metrics gathered so far, and starts over.
+	pub fn take_metrics(&self) -> Metrics {
+		std::mem::take(&mut *self.metrics.lock().unwrap())
+	}
+}
+
+impl Default for Scheduler {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Metrics {
+	pub fn mean(&self) -> f32 {
+		self.total as f32 / self.ticks.max(1) as f32
+	}
+}
+
+impl std::fmt::Display for Metrics {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(
+			f,
+			"plans per tick: {:.1} mean, {} max, {} last, {} deferred",
+			self.mean(),
+			self.max,
+			self.last,
+			self.deferred
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn slots_are_staggered_across_ticks() {
+		let scheduler = Scheduler::new();
+		let slots: Vec<_> = (0..4).map(|_| scheduler.slot()).collect();
+
+		for _ in 0..8 {
+			scheduler.begin_tick(usize::MAX);
+			let due: Vec<_> = slots.iter().filter(|&&s| scheduler.due(s, 4)).collect();
+			assert_eq!(due, [&((scheduler.tick() % 4) as usize)]);
+		}
+
+		//every slot is due every tick at a period of one
+		assert!(slots.iter().all(|&s| scheduler.due(s, 1)));
+		assert!(slots.iter().all(|&s| scheduler.due(s, 0)));
+	}
+
+	#[test]
+	fn budget_defers_plans_to_later_ticks() {
+		let scheduler = Scheduler::new();
+
+		scheduler.begin_tick(2);
+		let taken: Vec<_> = (0..5).map(|_| scheduler.try_plan()).collect();
+		assert_eq!(taken, [true, true, false, false, false]);
+
+		scheduler.begin_tick(10);
+		assert!(scheduler.try_plan());
+		scheduler.begin_tick(10);
+
+		let metrics = scheduler.take_metrics();
+		assert_eq!(metrics.ticks, 3);
+		assert_eq!(metrics.total, 3);
+		assert_eq!(metrics.max, 2);
+		assert_eq!(metrics.last, 1);
+		assert_eq!(metrics.deferred, 3);
+		assert_eq!(metrics.mean(), 1.);
+
+		assert_eq!(scheduler.take_metrics().ticks, 0);
+	}
+}
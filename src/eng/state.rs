@@ -1,6 +1,7 @@
 use super::*;
 
-use crate::window::WinApi;
+use crate::window::{Align, Text, WinApi};
+use ui::{Label, UIElement, UIRect};
 use winit::event::VirtualKeyCode;
 use winit::event_loop::EventLoop;
 
@@ -8,19 +9,37 @@ pub struct GameState<World: Root> {
 	pub(super) api: WinApi,
 	messenger: Messenger,
 	world: World,
+	//in the top right corner of the screen
+	stats: Label,
 }
 
 impl<World: Root> GameState<World> {
 	pub fn new(event_loop: &EventLoop<()>) -> Self {
 		let api = WinApi::new::<World::Texture>(event_loop);
+
+		let mut stats = Label::new(
+			Text::new("", cgmath::vec2(0., 0.))
+				.size(0.05)
+				.color((0., 0., 0., 1.))
+				.align(Align::Right),
+		);
+		stats.rect_mut().offset = cgmath::vec2(0.98, 0.95);
+		stats.propagate_global(&UIRect::default());
+
 		Self {
 			world: World::init(),
 			messenger: Messenger::new(),
+			stats,
 			api,
 		}
 	}
 
 	pub fn step(&mut self) {
+		//Most relaxed objects plan at the same frequency as the frame
+		//rate, so this mostly matters after many are created at once.
+		const PLAN_BUDGET: usize = 10_000;
+		self.api.external.scheduler.begin_tick(PLAN_BUDGET);
+
 		self.world
 			.plan(&(), &self.api.external, &self.messenger.sender());
 		self.world.update(&self.api.external, &self.messenger);
@@ -50,11 +69,20 @@ impl<World: Root> GameState<World> {
 	}
 
 	pub fn draw(&mut self) {
+		self.api.reload_assets();
 		self.api.clear();
 
 		self.world.render(&self.api.external, &mut self.api.output);
+		self.stats.render(&self.api.external, &mut self.api.output);
 
 		self.api.draw();
+		self.api
+			.record(|external, out| self.world.render(external, out));
+	}
+
+	//Shown in the corner of the screen.
+	pub fn set_stats(&mut self, stats: String) {
+		self.stats.text.string = stats;
 	}
 
 	pub fn cleanup(&mut self) {
@@ -1,8 +1,50 @@
 use super::*;
-use crate::window::TextureType;
+use crate::window::{SpriteSheet, TextureType};
 use std::time::Instant;
 
-type Curve = fn(f32) -> f32;
+//Maps the proportion of an animation elapsed onto the proportion of
+//it to show, both from 0 to 1.
+pub type Curve = fn(f32) -> f32;
+
+pub mod ease {
+	use super::Curve;
+	use std::f32::consts::*;
+
+	pub const LINEAR: Curve = |f| f;
+	pub const FIRST: Curve = |_| 0.;
+	pub const LAST: Curve = |_| 1.;
+	pub const REVERSE: Curve = |f| 1. - f;
+	pub const SIN: Curve = |f| (1. - (f * PI).cos()) / 2.;
+	pub const SIN_SQ: Curve = |f| SIN(f).powf(2.);
+	pub const REV_SIN_SQ: Curve = |f| SIN(1.0 - f).powf(2.);
+	pub const SIN_BOUNCE: Curve = |f| SIN(2. * f);
+
+	pub const CUBIC_IN: Curve = |f| f * f * f;
+	pub const CUBIC_OUT: Curve = |f| 1. - (1. - f).powi(3);
+	pub const CUBIC_IN_OUT: Curve = |f| {
+		if f < 0.5 {
+			4. * f * f * f
+		} else {
+			1. - (2. - 2. * f).powi(3) / 2.
+		}
+	};
+
+	//overshoots and springs back, like something released under tension
+	pub const ELASTIC_OUT: Curve = |f| {
+		if f <= 0. || f >= 1. {
+			f
+		} else {
+			2f32.powf(-10. * f) * ((10. * f - 0.75) * TAU / 3.).sin() + 1.
+		}
+	};
+
+	//pulls back slightly before moving
+	pub const BACK_IN: Curve = |f| {
+		const K: f32 = 1.70158;
+		(K + 1.) * f * f * f - K * f * f
+	};
+	pub const BACK_OUT: Curve = |f| 1. - BACK_IN(1. - f);
+}
 
 #[derive(Clone)]
 pub struct Animation<Texture: TextureType> {
@@ -11,18 +53,20 @@ pub struct Animation<Texture: TextureType> {
 	pub duration: f32,
 	pub curve: Curve,
 	pub repeat: f32, //Use f32::INFINITY to repeat forever
+	//Plays only the frames of this clip of the sprite sheet, each for its
+	//share of the clip's length, rather than every frame equally.
+	pub clip: Option<&'static str>,
 }
 
-use std::f32::consts::*;
 impl<Texture: TextureType> Animation<Texture> {
-	pub const LINEAR: Curve = |f| f;
-	pub const FIRST: Curve = |_| 0.;
-	pub const LAST: Curve = |_| 1.;
-	pub const REVERSE: Curve = |f| 1. - f;
-	pub const SIN: Curve = |f| (1. - (f * PI).cos()) / 2.;
-	pub const SIN_SQ: Curve = |f| Self::SIN(f).powf(2.);
-	pub const REV_SIN_SQ: Curve = |f| Self::SIN(1.0 - f).powf(2.);
-	pub const SIN_BOUNCE: Curve = |f| Self::SIN(2. * f);
+	pub const LINEAR: Curve = ease::LINEAR;
+	pub const FIRST: Curve = ease::FIRST;
+	pub const LAST: Curve = ease::LAST;
+	pub const REVERSE: Curve = ease::REVERSE;
+	pub const SIN: Curve = ease::SIN;
+	pub const SIN_SQ: Curve = ease::SIN_SQ;
+	pub const REV_SIN_SQ: Curve = ease::REV_SIN_SQ;
+	pub const SIN_BOUNCE: Curve = ease::SIN_BOUNCE;
 
 	pub fn new(texture: Texture, duration: f32, curve: fn(f32) -> f32, repeat: f32) -> Self {
 		Self {
@@ -31,12 +75,33 @@ impl<Texture: TextureType> Animation<Texture> {
 			duration,
 			curve,
 			repeat,
+			clip: None,
+		}
+	}
+
+	//Plays a clip at the speed given by its frame durations.
+	pub fn play(texture: Texture, sheet: &SpriteSheet, clip: &'static str, repeat: f32) -> Self {
+		Self {
+			clip: Some(clip),
+			..Self::new(
+				texture,
+				sheet.clip_duration(clip).unwrap_or(1.),
+				Self::LINEAR,
+				repeat,
+			)
+		}
+	}
+
+	pub fn with_clip(self, clip: &'static str) -> Self {
+		Self {
+			clip: Some(clip),
+			..self
 		}
 	}
 
 	pub fn frame(&self, external: &External) -> Instance {
 		let elapsed = self.age(external.now);
-		let frames = self.texture.frame_count();
+		let sheet = external.sheet(self.texture);
 
 		let reps_elapsed = elapsed / self.duration;
 
@@ -46,11 +111,18 @@ impl<Texture: TextureType> Animation<Texture> {
 			reps_elapsed
 		};
 
-		let frame = (frames as f32 * (self.curve)(proportion.fract())) as u32;
+		let progress = (self.curve)(proportion.fract()).min(1. - f32::EPSILON);
+
+		let clip = self
+			.clip
+			.and_then(|clip| Some(clip).zip(sheet.clip_duration(clip)));
+
+		let frame = match clip {
+			Some((clip, length)) => sheet.clip_frame(clip, progress * length).unwrap_or(0),
+			None => (sheet.frame_count() as f32 * progress) as u32,
+		};
 
-		external
-			.instance(self.texture)
-			.nth_frame(frame.clamp(0, frames - 1), frames)
+		sheet.frame(external.instance(self.texture), frame)
 	}
 
 	pub fn finished(&self, now: Instant) -> bool {
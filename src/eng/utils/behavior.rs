@@ -0,0 +1,321 @@
+//Behaviour trees, evaluated from the root on every tick. Evaluation only
+//reads its surroundings and writes to a blackboard owned by the agent,
+//so it belongs in GameObject::plan and may run on many threads at once.
+//The agent then acts on its blackboard in GameObject::update.
+
+use super::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Status {
+	Success,
+	Failure,
+	Running,
+}
+
+//Everything a tree may look at while planning.
+pub struct Senses<'a, Agent, Scene> {
+	pub agent: &'a Agent,
+	pub scene: &'a Scene,
+	pub messenger: &'a Sender<Dispatch>,
+}
+
+pub type Condition<A, S, B> = fn(&Senses<A, S>, &B) -> bool;
+pub type Task<A, S, B> = fn(&Senses<A, S>, &mut B) -> Status;
+
+pub enum Behavior<A, S, B> {
+	//Runs children in order until one doesn't succeed.
+	Sequence(Vec<Behavior<A, S, B>>),
+	//Runs children in order until one doesn't fail.
+	Selector(Vec<Behavior<A, S, B>>),
+	//Runs every child, succeeding once enough of them have.
+	Parallel(usize, Vec<Behavior<A, S, B>>),
+
+	Invert(Box<Behavior<A, S, B>>),
+	AlwaysSucceed(Box<Behavior<A, S, B>>),
+	//Only runs the child with the given probability, failing otherwise.
+	Chance(f32, Box<Behavior<A, S, B>>),
+
+	Condition(Condition<A, S, B>),
+	Task(Task<A, S, B>),
+
+	//Runs the child, which may move the agent between the named states.
+	//Only read when drawing the agent's states as a graph.
+	Moves(&'static [Edge], Box<Behavior<A, S, B>>),
+}
+
+//A change of state, from and to the states of the given names.
+pub type Edge = (&'static str, &'static str);
+
+impl<A, S, B> Behavior<A, S, B> {
+	pub fn tick(&self, senses: &Senses<A, S>, board: &mut B) -> Status {
+		use Behavior::*;
+		match self {
+			Sequence(children) => children
+				.iter()
+				.map(|child| child.tick(senses, board))
+				.find(|&status| status != Status::Success)
+				.unwrap_or(Status::Success),
+
+			Selector(children) => children
+				.iter()
+				.map(|child| child.tick(senses, board))
+				.find(|&status| status != Status::Failure)
+				.unwrap_or(Status::Failure),
+
+			Parallel(threshold, children) => {
+				let (mut succeeded, mut failed) = (0, 0);
+				for child in children {
+					match child.tick(senses, board) {
+						Status::Success => succeeded += 1,
+						Status::Failure => failed += 1,
+						Status::Running => {}
+					}
+				}
+
+				if succeeded >= *threshold {
+					Status::Success
+				} else if children.len() - failed < *threshold {
+					Status::Failure
+				} else {
+					Status::Running
+				}
+			}
+
+			Invert(child) => match child.tick(senses, board) {
+				Status::Success => Status::Failure,
+				Status::Failure => Status::Success,
+				Status::Running => Status::Running,
+			},
+
+			AlwaysSucceed(child) => {
+				child.tick(senses, board);
+				Status::Success
+			}
+
+			Chance(p, child) => {
+				if probability(*p) {
+					child.tick(senses, board)
+				} else {
+					Status::Failure
+				}
+			}
+
+			Condition(condition) => {
+				if condition(senses, board) {
+					Status::Success
+				} else {
+					Status::Failure
+				}
+			}
+
+			Task(task) => task(senses, board),
+
+			Moves(_, child) => child.tick(senses, board),
+		}
+	}
+
+	//Every change of state noted anywhere in the tree.
+	pub fn edges(&self) -> Vec<Edge> {
+		use Behavior::*;
+		match self {
+			Sequence(children) | Selector(children) | Parallel(_, children) => {
+				children.iter().flat_map(|child| child.edges()).collect()
+			}
+			Invert(child) | AlwaysSucceed(child) | Chance(_, child) => child.edges(),
+			Moves(edges, child) => edges.iter().copied().chain(child.edges()).collect(),
+			Condition(_) | Task(_) => vec![],
+		}
+	}
+
+	pub fn sequence(children: impl IntoIterator<Item = Self>) -> Self {
+		Self::Sequence(children.into_iter().collect())
+	}
+
+	pub fn selector(children: impl IntoIterator<Item = Self>) -> Self {
+		Self::Selector(children.into_iter().collect())
+	}
+
+	pub fn parallel(threshold: usize, children: impl IntoIterator<Item = Self>) -> Self {
+		Self::Parallel(threshold, children.into_iter().collect())
+	}
+
+	pub fn invert(self) -> Self {
+		Self::Invert(Box::new(self))
+	}
+
+	pub fn always_succeed(self) -> Self {
+		Self::AlwaysSucceed(Box::new(self))
+	}
+
+	pub fn chance(self, p: f32) -> Self {
+		Self::Chance(p, Box::new(self))
+	}
+
+	pub fn moves(self, edges: &'static [Edge]) -> Self {
+		Self::Moves(edges, Box::new(self))
+	}
+
+	//Runs `then` only if `condition` holds.
+	pub fn guard(condition: Condition<A, S, B>, then: Self) -> Self {
+		Self::Sequence(vec![Self::Condition(condition), then])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use Status::*;
+
+	//Tasks record that they ran, and `progress` runs for two ticks before
+	//it succeeds.
+	#[derive(Default)]
+	struct Board {
+		ran: Vec<&'static str>,
+		progress: u32,
+	}
+
+	type Tree = Behavior<(), (), Board>;
+
+	fn succeed(_: &Senses<(), ()>, board: &mut Board) -> Status {
+		board.ran.push("succeed");
+		Success
+	}
+
+	fn fail(_: &Senses<(), ()>, board: &mut Board) -> Status {
+		board.ran.push("fail");
+		Failure
+	}
+
+	fn progress(_: &Senses<(), ()>, board: &mut Board) -> Status {
+		board.ran.push("progress");
+		board.progress += 1;
+		if board.progress > 2 {
+			Success
+		} else {
+			Running
+		}
+	}
+
+	//Ticks the tree once, returning its status and the tasks it ran.
+	fn tick(tree: &Tree, board: &mut Board) -> (Status, Vec<&'static str>) {
+		let (messenger, _) = std::sync::mpsc::channel();
+		let senses = Senses {
+			agent: &(),
+			scene: &(),
+			messenger: &messenger,
+		};
+		let status = tree.tick(&senses, board);
+		(status, std::mem::take(&mut board.ran))
+	}
+
+	fn run(tree: &Tree) -> (Status, Vec<&'static str>) {
+		tick(tree, &mut Board::default())
+	}
+
+	#[test]
+	fn sequence_stops_at_first_failure() {
+		let tree = Tree::sequence([Tree::Task(succeed), Tree::Task(fail), Tree::Task(succeed)]);
+		assert_eq!(run(&tree), (Failure, vec!["succeed", "fail"]));
+
+		let tree = Tree::sequence([Tree::Task(succeed), Tree::Task(succeed)]);
+		assert_eq!(run(&tree), (Success, vec!["succeed", "succeed"]));
+	}
+
+	#[test]
+	fn selector_stops_at_first_success() {
+		let tree = Tree::selector([Tree::Task(fail), Tree::Task(succeed), Tree::Task(fail)]);
+		assert_eq!(run(&tree), (Success, vec!["fail", "succeed"]));
+
+		let tree = Tree::selector([Tree::Task(fail), Tree::Task(fail)]);
+		assert_eq!(run(&tree), (Failure, vec!["fail", "fail"]));
+	}
+
+	//A running child holds back the rest of a sequence until it finishes,
+	//with the children before it re-evaluated on each tick.
+	#[test]
+	fn running_resumes_on_later_ticks() {
+		let tree = Tree::sequence([Tree::Task(succeed), Tree::Task(progress), Tree::Task(fail)]);
+		let mut board = Board::default();
+
+		assert_eq!(
+			tick(&tree, &mut board),
+			(Running, vec!["succeed", "progress"])
+		);
+		assert_eq!(
+			tick(&tree, &mut board),
+			(Running, vec!["succeed", "progress"])
+		);
+		assert_eq!(
+			tick(&tree, &mut board),
+			(Failure, vec!["succeed", "progress", "fail"])
+		);
+
+		let tree = Tree::selector([Tree::Task(fail), Tree::Task(progress), Tree::Task(succeed)]);
+		assert_eq!(run(&tree), (Running, vec!["fail", "progress"]));
+	}
+
+	#[test]
+	fn parallel_waits_for_threshold() {
+		let tree = Tree::parallel(
+			2,
+			[Tree::Task(succeed), Tree::Task(fail), Tree::Task(progress)],
+		);
+		let mut board = Board::default();
+
+		assert_eq!(tick(&tree, &mut board).0, Running);
+		assert_eq!(tick(&tree, &mut board).0, Running);
+		assert_eq!(
+			tick(&tree, &mut board),
+			(Success, vec!["succeed", "fail", "progress"])
+		);
+
+		//fails as soon as too few children are left to reach it
+		let tree = Tree::parallel(
+			2,
+			[Tree::Task(fail), Tree::Task(progress), Tree::Task(fail)],
+		);
+		assert_eq!(run(&tree).0, Failure);
+	}
+
+	#[test]
+	fn decorators() {
+		assert_eq!(run(&Tree::Task(succeed).invert()).0, Failure);
+		assert_eq!(run(&Tree::Task(fail).invert()).0, Success);
+		assert_eq!(run(&Tree::Task(progress).invert()).0, Running);
+
+		assert_eq!(
+			run(&Tree::Task(fail).always_succeed()),
+			(Success, vec!["fail"])
+		);
+		assert_eq!(run(&Tree::Task(progress).always_succeed()).0, Success);
+
+		assert_eq!(run(&Tree::Task(succeed).chance(0.)), (Failure, vec![]));
+		assert_eq!(
+			run(&Tree::Task(succeed).chance(1.)),
+			(Success, vec!["succeed"])
+		);
+	}
+
+	#[test]
+	fn edges_are_collected_without_changing_status() {
+		let tree = Tree::selector([
+			Tree::Task(fail).moves(&[("a", "b")]),
+			Tree::sequence([Tree::Task(succeed).moves(&[("b", "c"), ("b", "a")])]).invert(),
+		]);
+
+		assert_eq!(tree.edges(), [("a", "b"), ("b", "c"), ("b", "a")]);
+		assert_eq!(run(&tree), (Failure, vec!["fail", "succeed"]));
+	}
+
+	#[test]
+	fn guard_only_runs_when_condition_holds() {
+		let tree = Tree::guard(|_, board| board.progress > 0, Tree::Task(succeed));
+		assert_eq!(run(&tree), (Failure, vec![]));
+
+		let mut board = Board {
+			progress: 1,
+			..Default::default()
+		};
+		assert_eq!(tick(&tree, &mut board), (Success, vec!["succeed"]));
+	}
+}
@@ -0,0 +1,220 @@
+//Rasterised densities of spatial containers, i.e. for heatmaps of ant
+//traffic or pheromone strength over some region of the world.
+
+use crate::window::{Heatmap, Ramp};
+use std::path::Path;
+
+pub struct Density {
+	pub lo: (f32, f32),
+	pub hi: (f32, f32),
+	pub width: u32,
+	pub height: u32,
+	//row-major, with the first row at the bottom of the region
+	pub values: Vec<f32>,
+}
+
+impl Density {
+	pub fn new(lo: (f32, f32), hi: (f32, f32), (width, height): (u32, u32)) -> Self {
+		debug_assert!(lo.0 < hi.0 && lo.1 < hi.1);
+		Self {
+			lo,
+			hi,
+			width,
+			height,
+			values: vec![0.; (width * height) as usize],
+		}
+	}
+
+	pub fn pixel_of(&self, (x, y): (f32, f32)) -> Option<(u32, u32)> {
+		let u = (x - self.lo.0) / (self.hi.0 - self.lo.0);
+		let v = (y - self.lo.1) / (self.hi.1 - self.lo.1);
+
+		if (0. ..1.).contains(&u) && (0. ..1.).contains(&v) {
+			Some((
+				(u * self.width as f32) as u32,
+				(v * self.height as f32) as u32,
+			))
+		} else {
+			None
+		}
+	}
+
+	pub fn splat(&mut self, pos: (f32, f32), weight: f32) {
+		if let Some((px, py)) = self.pixel_of(pos) {
+			self.values[(py * self.width + px) as usize] += weight;
+		}
+	}
+
+	pub fn get(&self, px: u32, py: u32) -> f32 {
+		self.values[(py * self.width + px) as usize]
+	}
+
+	//Averages each pixel with those up to `radius` away along either axis,
+	//so that sparse splats join up.
+	pub fn blurred(&self, radius: u32) -> Self {
+		let r = radius as i64;
+		let (w, h) = (self.width as i64, self.height as i64);
+
+		let pass = |values: &[f32], step: (i64, i64)| -> Vec<f32> {
+			(0..h)
+				.flat_map(|y| (0..w).map(move |x| (x, y)))
+				.map(|(x, y)| {
+					let sum: f32 = (-r..=r)
+						.map(|i| (x + i * step.0, y + i * step.1))
+						.filter(|&(x, y)| (0..w).contains(&x) && (0..h).contains(&y))
+						.map(|(x, y)| values[(y * w + x) as usize])
+						.sum();
+					sum / (2 * r + 1) as f32
+				})
+				.collect()
+		};
+
+		Self {
+			lo: self.lo,
+			hi: self.hi,
+			width: self.width,
+			height: self.height,
+			values: pass(&pass(&self.values, (1, 0)), (0, 1)),
+		}
+	}
+
+	pub fn max(&self) -> f32 {
+		self.values.iter().copied().fold(0., f32::max)
+	}
+
+	//`ramp` receives each value normalised against the maximum.
+	pub fn to_image<R>(&self, mut ramp: R) -> image::RgbaImage
+	where
+		R: FnMut(f32) -> image::Rgba<u8>,
+	{
+		let max = self.max();
+		let norm = if max > 0. { 1. / max } else { 0. };
+
+		//flipped vertically, since image rows go downward
+		image::RgbaImage::from_fn(self.width, self.height, |px, py| {
+			ramp(norm * self.get(px, self.height - 1 - py))
+		})
+	}
+
+	//Values normalised against the maximum, to be coloured on the GPU.
+	pub fn heatmap(&self, ramp: Ramp) -> Heatmap {
+		let max = self.max();
+		let norm = if max > 0. { 1. / max } else { 0. };
+
+		//flipped vertically, since heatmap rows go downward
+		let values = (0..self.height)
+			.rev()
+			.flat_map(|py| (0..self.width).map(move |px| (px, py)))
+			.map(|(px, py)| norm * self.get(px, py))
+			.collect();
+
+		Heatmap {
+			width: self.width,
+			height: self.height,
+			values,
+			ramp,
+		}
+	}
+
+	pub fn save_png<P: AsRef<Path>>(&self, path: P) -> image::ImageResult<()> {
+		self.to_image(Self::HEAT)
+			.save_with_format(path, image::ImageFormat::Png)
+	}
+
+	//black -> red -> yellow -> white
+	pub const HEAT: fn(f32) -> image::Rgba<u8> = |f| {
+		let channel = |lo: f32| (255. * ((3. * f - lo).clamp(0., 1.))) as u8;
+		image::Rgba([channel(0.), channel(1.), channel(2.), 255])
+	};
+
+	//A single colour, fading out to nothing at zero.
+	pub fn tint((r, g, b): (u8, u8, u8)) -> impl Fn(f32) -> image::Rgba<u8> {
+		move |f| image::Rgba([r, g, b, (255. * f.clamp(0., 1.)) as u8])
+	}
+
+	//Makes a ramp fade out towards zero, reaching full opacity at `opaque`,
+	//for drawing over other things.
+	pub fn fading<R>(ramp: R, opaque: f32) -> impl Fn(f32) -> image::Rgba<u8>
+	where
+		R: Fn(f32) -> image::Rgba<u8>,
+	{
+		move |f| {
+			let mut colour = ramp(f);
+			colour.0[3] = (colour.0[3] as f32 * (f / opaque).clamp(0., 1.)) as u8;
+			colour
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::eng::utils::{Grid, Griddable, SpatialIndex};
+
+	struct Point((f32, f32));
+
+	impl Griddable for Point {
+		fn pos(&self) -> (f32, f32) {
+			self.0
+		}
+	}
+
+	fn total(density: &Density) -> f32 {
+		density.values.iter().sum()
+	}
+
+	#[test]
+	fn splat_adds_weights_and_drops_points_outside() {
+		let mut density = Density::new((0., 0.), (4., 2.), (4, 2));
+		density.splat((1.5, 0.5), 1.);
+		density.splat((1.25, 0.75), 2.);
+		density.splat((3.5, 1.5), 0.5);
+		//the upper bounds are exclusive
+		for pos in [(-0.5, 1.), (4., 1.), (2., 2.), (2., -0.1)] {
+			density.splat(pos, 10.);
+		}
+
+		assert_eq!(density.get(1, 0), 3.);
+		assert_eq!(density.get(3, 1), 0.5);
+		assert_eq!(total(&density), 3.5);
+	}
+
+	#[test]
+	fn blurring_keeps_the_mass_away_from_the_edges() {
+		let mut density = Density::new((0., 0.), (9., 9.), (9, 9));
+		density.splat((4.5, 4.5), 9.);
+		density.splat((3.5, 5.5), 3.);
+
+		let blurred = density.blurred(2);
+		assert!((total(&blurred) - 12.).abs() < 1e-4);
+		assert!(blurred.max() < density.max());
+	}
+
+	#[test]
+	fn weighted_density_is_clipped_to_the_box() {
+		//outside the box, but within the circle around it that is queried
+		let outside = [(-0.5, 7.5), (10.4, 7.5), (5., 4.9)];
+		let inside = [(0.5, 5.5), (9.5, 9.5)];
+
+		let mut grid = Grid::new(2.);
+		grid.extend(outside.into_iter().chain(inside).map(Point));
+
+		assert_eq!(grid.query_at((5., 7.5), 5.5).count(), 5);
+
+		let density = grid.weighted_density((0., 5.), (10., 10.), (10, 5), |_| 1.);
+		assert_eq!(total(&density), inside.len() as f32);
+		assert_eq!(density.get(0, 0), 1.);
+		assert_eq!(density.get(9, 4), 1.);
+	}
+
+	#[test]
+	fn heatmap_is_normalised_from_the_top_row() {
+		let mut density = Density::new((0., 0.), (2., 2.), (2, 2));
+		density.splat((0.5, 0.5), 2.);
+		density.splat((1.5, 1.5), 4.);
+
+		let heatmap = density.heatmap(Ramp::new(Density::HEAT));
+		assert_eq!((heatmap.width, heatmap.height), (2, 2));
+		assert_eq!(heatmap.values, [0., 1., 0.5, 0.]);
+	}
+}
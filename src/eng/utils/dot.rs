@@ -0,0 +1,155 @@
+//Graphviz exports of state machines, so that the transitions agents
+//actually take during a run can be compared with the ones designed.
+
+use std::fmt::Write;
+use std::sync::Mutex;
+
+pub trait StateGraph: Copy + Eq + 'static {
+	fn states() -> Vec<Self>;
+	fn name(&self) -> &'static str;
+
+	//transitions the machine is designed to take
+	fn transitions() -> Vec<(Self, Self)>;
+
+	fn named(name: &str) -> Option<Self> {
+		Self::states()
+			.into_iter()
+			.find(|state| state.name() == name)
+	}
+}
+
+//Counts of observed transitions, usually kept in a static shared by every
+//agent of a kind. Transitions should be recorded during updates, so that
+//the lock is never contended by planning threads.
+pub struct TransitionLog<S> {
+	counts: Mutex<Vec<((S, S), usize)>>,
+}
+
+impl<S: Copy + Eq> TransitionLog<S> {
+	pub const fn new() -> Self {
+		Self {
+			counts: Mutex::new(vec![]),
+		}
+	}
+
+	pub fn record(&self, from: S, to: S) {
+		let mut counts = self.counts.lock().unwrap();
+		match counts.iter_mut().find(|(edge, _)| *edge == (from, to)) {
+			Some((_, count)) => *count += 1,
+			None => counts.push(((from, to), 1)),
+		}
+	}
+
+	pub fn count(&self, from: S, to: S) -> usize {
+		let counts = self.counts.lock().unwrap();
+		counts
+			.iter()
+			.find(|(edge, _)| *edge == (from, to))
+			.map_or(0, |&(_, count)| count)
+	}
+}
+
+impl<S: StateGraph> TransitionLog<S> {
+	//Designed transitions are drawn solid and observed-only ones dashed,
+	//with thickness growing with the number of times each was taken.
+	pub fn to_dot(&self, title: &str) -> String {
+		let counts = self.counts.lock().unwrap().clone();
+		let total = counts.iter().map(|&(_, c)| c).sum::<usize>().max(1);
+
+		let mut edges = S::transitions()
+			.into_iter()
+			.map(|edge| (edge, true))
+			.collect::<Vec<_>>();
+
+		for &(edge, _) in &counts {
+			if !edges.iter().any(|&(e, _)| e == edge) {
+				edges.push((edge, false));
+			}
+		}
+
+		let mut dot = String::new();
+		writeln!(dot, "digraph \"{}\" {{", escape(title)).unwrap();
+
+		for state in S::states() {
+			writeln!(dot, "\t\"{}\";", escape(state.name())).unwrap();
+		}
+
+		for ((from, to), designed) in edges {
+			let count = counts
+				.iter()
+				.find(|&&(e, _)| e == (from, to))
+				.map_or(0, |&(_, c)| c);
+			let width = 1. + 4. * count as f32 / total as f32;
+			let style = if designed { "solid" } else { "dashed" };
+
+			writeln!(
+				dot,
+				"\t\"{}\" -> \"{}\" [label=\"{}\", penwidth={:.2}, style={}];",
+				escape(from.name()),
+				escape(to.name()),
+				count,
+				width,
+				style
+			)
+			.unwrap();
+		}
+
+		dot.push_str("}\n");
+		dot
+	}
+
+	pub fn save_dot<P: AsRef<std::path::Path>>(&self, path: P, title: &str) -> std::io::Result<()> {
+		if let Some(dir) = path.as_ref().parent() {
+			std::fs::create_dir_all(dir)?;
+		}
+		std::fs::write(path, self.to_dot(title))
+	}
+}
+
+impl<S: Copy + Eq> Default for TransitionLog<S> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+//for use inside a quoted DOT ID
+fn escape(id: &str) -> String {
+	id.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Clone, Copy, PartialEq, Eq)]
+	enum Quoted {
+		A,
+		B,
+	}
+
+	impl StateGraph for Quoted {
+		fn states() -> Vec<Self> {
+			vec![Quoted::A, Quoted::B]
+		}
+
+		fn name(&self) -> &'static str {
+			match self {
+				Quoted::A => "say \"hi\"",
+				Quoted::B => "C:\\",
+			}
+		}
+
+		fn transitions() -> Vec<(Self, Self)> {
+			vec![(Quoted::A, Quoted::B)]
+		}
+	}
+
+	#[test]
+	fn escapes_quotes_and_backslashes() {
+		let log = TransitionLog::<Quoted>::new();
+		let dot = log.to_dot("a \"title\"");
+
+		assert!(dot.starts_with("digraph \"a \\\"title\\\"\" {"));
+		assert!(dot.contains("\t\"say \\\"hi\\\"\" -> \"C:\\\\\" [label=\"0\""));
+	}
+}
@@ -1,9 +1,13 @@
 use rayon::iter::*;
 use std::ops::{Index, IndexMut};
 
+//Serializing directly preserves the layout of free slots, so that
+//indices held elsewhere remain valid. See `compact` otherwise.
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct FreeList<T> {
 	inner: Vec<Elem<T>>,
 	free: Option<usize>,
+	len: usize,
 }
 
 impl<T> FreeList<T> {
@@ -11,6 +15,7 @@ impl<T> FreeList<T> {
 		Self {
 			inner: vec![],
 			free: None,
+			len: 0,
 		}
 	}
 
@@ -33,13 +38,16 @@ impl<T> FreeList<T> {
 	}
 
 	pub fn count(&self) -> usize {
-		self.inner
-			.iter()
-			.filter(|e| matches!(e, Elem::Obj(_)))
-			.count()
+		self.len
+	}
+
+	//whether most slots are free, and compacting would be worthwhile
+	pub fn sparse(&self) -> bool {
+		2 * self.len < self.inner.len()
 	}
 
 	pub fn insert(&mut self, item: T) -> usize {
+		self.len += 1;
 		if let Some(first_free) = self.free {
 			self.free = match self.inner[first_free] {
 				Elem::NextFree(next) => Some(next),
@@ -55,6 +63,12 @@ impl<T> FreeList<T> {
 	}
 
 	pub fn remove(&mut self, index: usize) -> Option<T> {
+		//freeing an empty slot twice would corrupt the free chain
+		if !matches!(self.inner[index], Elem::Obj(_)) {
+			return None;
+		}
+
+		self.len -= 1;
 		let obj = std::mem::replace(&mut self.inner[index], Elem::LastFree);
 
 		if let Some(free) = self.free {
@@ -94,6 +108,64 @@ impl<T> FreeList<T> {
 		}
 	}
 
+	//Removes every element matching the predicate, along with the
+	//index it was stored at.
+	pub fn drain_filter<P: FnMut(&mut T) -> bool>(&mut self, mut predicate: P) -> Vec<(usize, T)> {
+		let matching = self
+			.iter_mut_with_index()
+			.filter_map(|(i, item)| Some(i).filter(|_| predicate(item)))
+			.collect::<Vec<_>>();
+
+		matching
+			.into_iter()
+			.filter_map(|i| self.remove(i).map(|item| (i, item)))
+			.collect()
+	}
+
+	//Moves all elements to the front, preserving their order, and
+	//releases the remaining slots. The returned table maps every old
+	//index to its new one, so that indices held elsewhere can be updated.
+	pub fn compact(&mut self) -> Vec<Option<usize>> {
+		let mut remap = Vec::with_capacity(self.inner.len());
+		let mut next = 0;
+
+		let old = std::mem::take(&mut self.inner);
+		for elem in old {
+			if let Elem::Obj(item) = elem {
+				remap.push(Some(next));
+				self.inner.push(Elem::Obj(item));
+				next += 1;
+			} else {
+				remap.push(None);
+			}
+		}
+
+		self.inner.shrink_to_fit();
+		self.free = None;
+
+		remap
+	}
+
+	pub fn iter_with_index(&self) -> impl Iterator<Item = (usize, &T)> {
+		self.inner
+			.iter()
+			.enumerate()
+			.filter_map(|(i, elem)| match elem {
+				Elem::Obj(item) => Some((i, item)),
+				_ => None,
+			})
+	}
+
+	pub fn iter_mut_with_index(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+		self.inner
+			.iter_mut()
+			.enumerate()
+			.filter_map(|(i, elem)| match elem {
+				Elem::Obj(item) => Some((i, item)),
+				_ => None,
+			})
+	}
+
 	pub fn iter(&self) -> impl Iterator<Item = &T> {
 		self.inner.iter().filter_map(|elem| match elem {
 			Elem::Obj(item) => Some(item),
@@ -109,6 +181,12 @@ impl<T> FreeList<T> {
 	}
 }
 
+impl<T> Default for FreeList<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl<T: Send + Sync> FreeList<T> {
 	pub fn par_iter(&self) -> impl ParallelIterator<Item = &T> {
 		self.inner.par_iter().filter_map(|elem| match elem {
@@ -144,8 +222,143 @@ impl<T> IndexMut<usize> for FreeList<T> {
 	}
 }
 
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 enum Elem<T> {
 	Obj(T),
 	NextFree(usize),
 	LastFree,
 }
+
+//For use with `#[serde(with = "freelist::compact")]`, when no indices
+//into the list need to survive. Only live elements are written, and
+//are read back into a list without free slots.
+#[cfg(feature = "serialize")]
+pub mod compact {
+	use super::*;
+	use serde::*;
+
+	pub fn serialize<T: Serialize, S: Serializer>(
+		list: &FreeList<T>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(list.iter())
+	}
+
+	pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<FreeList<T>, D::Error> {
+		let items = Vec::<T>::deserialize(deserializer)?;
+		Ok(FreeList {
+			len: items.len(),
+			inner: items.into_iter().map(Elem::Obj).collect(),
+			free: None,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn list(n: usize) -> FreeList<usize> {
+		let mut list = FreeList::new();
+		for i in 0..n {
+			assert_eq!(list.insert(i), i);
+		}
+		list
+	}
+
+	#[test]
+	fn reuses_freed_slots() {
+		let mut list = list(4);
+		assert_eq!(list.remove(1), Some(1));
+		assert_eq!(list.remove(1), None);
+		assert_eq!(list.count(), 3);
+
+		assert_eq!(list.insert(10), 1);
+		assert_eq!(list.insert(11), 4);
+		assert_eq!(list.count(), 5);
+	}
+
+	#[test]
+	fn drains_with_indices() {
+		let mut list = list(6);
+		let drained = list.drain_filter(|i| *i % 2 == 0);
+
+		assert_eq!(drained, vec![(0, 0), (2, 2), (4, 4)]);
+		assert_eq!(list.count(), 3);
+		assert_eq!(
+			list.iter_with_index().collect::<Vec<_>>(),
+			vec![(1, &1), (3, &3), (5, &5)]
+		);
+	}
+
+	#[test]
+	fn compacts_in_order() {
+		let mut list = list(6);
+		list.drain_filter(|i| *i != 2 && *i != 5);
+		assert!(list.sparse());
+
+		let remap = list.compact();
+		assert_eq!(remap, vec![None, None, Some(0), None, None, Some(1)]);
+		assert_eq!(list.slot_count(), 2);
+		assert!(!list.sparse());
+		assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![2, 5]);
+
+		//nothing is left free, so new elements go on the end
+		assert_eq!(list.insert(6), 2);
+	}
+}
+
+#[cfg(all(test, feature = "serialize"))]
+mod serialize_tests {
+	use super::*;
+
+	//0 to 5, with 1 and 3 removed
+	fn list() -> FreeList<usize> {
+		let mut list = FreeList::new();
+		for i in 0..6 {
+			list.insert(i);
+		}
+		list.remove(3);
+		list.remove(1);
+		list
+	}
+
+	#[test]
+	fn round_trips_with_free_slots() {
+		let text = ron::to_string(&list()).unwrap();
+		let mut back: FreeList<usize> = ron::from_str(&text).unwrap();
+
+		assert_eq!(back.count(), 4);
+		assert_eq!(back.slot_count(), 6);
+		assert_eq!(back.get(1), None);
+		assert_eq!(back.get(3), None);
+		assert_eq!(back.get(4), Some(&4));
+
+		//removed slots are still free, and reused last removed first
+		assert_eq!(back.insert(10), 1);
+		assert_eq!(back.insert(11), 3);
+		assert_eq!(back.insert(12), 6);
+	}
+
+	#[test]
+	fn round_trips_compacted() {
+		#[derive(serde::Serialize, serde::Deserialize)]
+		struct Wrapper {
+			#[serde(with = "compact")]
+			list: FreeList<usize>,
+		}
+
+		let text = ron::to_string(&Wrapper { list: list() }).unwrap();
+		let mut back: Wrapper = ron::from_str(&text).unwrap();
+
+		assert_eq!(back.list.slot_count(), 4);
+		assert!(!back.list.sparse());
+		assert_eq!(
+			back.list.iter().copied().collect::<Vec<_>>(),
+			vec![0, 2, 4, 5]
+		);
+		assert_eq!(back.list.insert(6), 4);
+	}
+}
@@ -1,15 +1,77 @@
 use super::*;
+use std::time::Instant;
+
+//States may be nested: a state with a parent is part of the parent's
+//sub-machine, and a state with an initial child is entered through it.
+//Flat machines can rely on the defaults.
+pub trait MachineState: Copy + Eq + 'static {
+	fn parent(&self) -> Option<Self> {
+		None
+	}
+
+	fn initial(&self) -> Option<Self> {
+		None
+	}
+}
+
+//Active states from the outermost to the innermost, along with the
+//time each was entered.
+#[derive(Clone)]
+pub struct Machine<S: MachineState> {
+	path: Vec<(S, Instant)>,
+	//where transitions between innermost states are counted
+	log: Option<&'static TransitionLog<S>>,
+}
 
 pub trait Automaton {
 	type Scene;
 	type Action;
-	type State: Copy + Eq;
+	//so that the transitions taken can be exported, see `save_transition_graph`
+	type State: MachineState + StateGraph;
+
+	fn machine(&self) -> &Machine<Self::State>;
+	fn machine_mut(&mut self) -> &mut Machine<Self::State>;
+
+	//Transition out of `state`, which is the current state or one of its
+	//ancestors. Returning None defers to the parent, so that transitions
+	//shared by a whole sub-machine need only be given once.
+	fn transition(&self, state: Self::State, external: &External) -> Option<Self::State>;
+
+	//Seconds after which `state` is left for the given state, if nothing
+	//else happens first.
+	fn timeout(&self, _state: Self::State) -> Option<(f32, Self::State)> {
+		None
+	}
+
+	fn enter(&mut self, _state: Self::State, _from: Self::State) {}
+	fn exit(&mut self, _state: Self::State, _to: Self::State) {}
+
+	//Called once per transition, after all exits and enters. `from` and
+	//`to` are the same when the current state was re-entered.
+	fn on_transition(&mut self, _from: Self::State, _to: Self::State) {}
+
+	fn state(&self) -> Self::State {
+		self.machine().state()
+	}
+
+	fn elapsed(&self, external: &External) -> f32 {
+		self.machine().elapsed(external.now)
+	}
 
-	fn state(&self) -> Self::State;
-	fn state_mut(&mut self) -> &mut Self::State;
-	fn enter_from(&mut self, _old: Self::State) {}
-	fn exit_to(&mut self, _new: Self::State) {}
-	fn next_state(&self, external: &External) -> Self::State;
+	//Writes the transitions taken by every machine sharing this one's log.
+	fn save_transition_graph<P: AsRef<std::path::Path>>(
+		&self,
+		path: P,
+		title: &str,
+	) -> std::io::Result<()> {
+		match self.machine().log {
+			Some(log) => log.save_dot(path, title),
+			None => Err(std::io::Error::new(
+				std::io::ErrorKind::NotFound,
+				"Machine has no transition log.",
+			)),
+		}
+	}
 
 	fn by_probability(&self, probability_table: &[(Self::State, f32)]) -> Self::State {
 		let mut rng = random();
@@ -22,6 +84,70 @@ pub trait Automaton {
 		self.state()
 	}
 
+	fn by_utility<I, F>(&self, reasoner: &Reasoner<Self::State, I>, sense: F) -> Self::State
+	where
+		I: Copy,
+		F: FnMut(I) -> f32,
+	{
+		reasoner.choose(sense).unwrap_or(self.state())
+	}
+
+	//Checks timeouts and transitions from the innermost state outward.
+	fn next_state(&self, external: &External) -> Option<Self::State> {
+		self.machine()
+			.path
+			.iter()
+			.rev()
+			.find_map(|&(state, entered)| {
+				let timed_out = self.timeout(state).and_then(|(limit, next)| {
+					let elapsed = external.now.duration_since(entered).as_secs_f32();
+					Some(next).filter(|_| elapsed > limit)
+				});
+				timed_out.or_else(|| self.transition(state, external))
+			})
+	}
+
+	//Exits up to the innermost state shared with the target's path, then
+	//enters down to the target. A transition to the current state or one
+	//of its ancestors leaves and re-enters it, restarting its clock.
+	fn transition_to(&mut self, target: Self::State, now: Instant) {
+		let old = self.state();
+		let new_path = Machine::path_to(target);
+		let depth = new_path.iter().position(|&s| s == target).unwrap();
+
+		let shared = self
+			.machine()
+			.path
+			.iter()
+			.zip(&new_path[..depth])
+			.take_while(|((a, _), b)| a == *b)
+			.count();
+
+		let new = *new_path.last().unwrap();
+
+		let exiting = self.machine().path[shared..]
+			.iter()
+			.rev()
+			.map(|&(state, _)| state)
+			.collect::<Vec<_>>();
+
+		for state in exiting {
+			self.exit(state, new);
+		}
+
+		self.machine_mut().path.truncate(shared);
+
+		for &state in &new_path[shared..] {
+			self.machine_mut().path.push((state, now));
+			self.enter(state, old);
+		}
+
+		if let Some(log) = self.machine().log {
+			log.record(old, new);
+		}
+		self.on_transition(old, new);
+	}
+
 	fn plan(&self, _scene: &Self::Scene, _external: &External, _messenger: &Sender<Dispatch>) {}
 
 	fn update(&mut self, _external: &External, _messenger: &Messenger) -> Option<Self::Action> {
@@ -32,15 +158,74 @@ pub trait Automaton {
 		None
 	}
 
-	fn render(&self, external: &External, out: &mut Vec<Instance>) {
+	fn layer(&self) -> Layer {
+		Layer::Entities
+	}
+
+	fn render(&self, external: &External, out: &mut Layers) {
 		if let Some(inst) = self.instance(external) {
-			external.clip(out, inst);
+			external.clip(out, self.layer(), inst);
 		}
 	}
 
 	fn cleanup(&mut self) {}
 }
 
+impl<S: MachineState> Machine<S> {
+	pub fn new(initial: S, now: Instant) -> Self {
+		Self {
+			path: Self::path_to(initial)
+				.into_iter()
+				.map(|state| (state, now))
+				.collect(),
+			log: None,
+		}
+	}
+
+	pub fn logged(self, log: &'static TransitionLog<S>) -> Self {
+		Self {
+			log: Some(log),
+			..self
+		}
+	}
+
+	pub fn state(&self) -> S {
+		self.path.last().unwrap().0
+	}
+
+	//whether `state` is the current state or one of its ancestors
+	pub fn is_in(&self, state: S) -> bool {
+		self.path.iter().any(|&(s, _)| s == state)
+	}
+
+	pub fn elapsed(&self, now: Instant) -> f32 {
+		now.duration_since(self.path.last().unwrap().1)
+			.as_secs_f32()
+	}
+
+	pub fn elapsed_in(&self, state: S, now: Instant) -> Option<f32> {
+		self.path
+			.iter()
+			.find(|&&(s, _)| s == state)
+			.map(|&(_, entered)| now.duration_since(entered).as_secs_f32())
+	}
+
+	//all states entered when moving to `target`, outermost first
+	fn path_to(target: S) -> Vec<S> {
+		let mut path = vec![target];
+		while let Some(parent) = path.last().unwrap().parent() {
+			path.push(parent);
+		}
+		path.reverse();
+
+		while let Some(child) = path.last().unwrap().initial() {
+			path.push(child);
+		}
+
+		path
+	}
+}
+
 impl<T: Automaton> GameObject for T {
 	type Scene = T::Scene;
 	type Action = T::Action;
@@ -50,19 +235,18 @@ impl<T: Automaton> GameObject for T {
 	}
 
 	fn update(&mut self, external: &External, messenger: &Messenger) -> Option<Self::Action> {
-		let old = self.state();
-		let new = self.next_state(external);
-
-		if new != old {
-			self.exit_to(new);
-			*self.state_mut() = new;
-			self.enter_from(old);
+		if let Some(next) = self.next_state(external) {
+			self.transition_to(next, external.now);
 		}
 
 		Automaton::update(self, external, messenger)
 	}
 
-	fn render(&self, context: &External, out: &mut Vec<Instance>) {
+	fn layer(&self) -> Layer {
+		Automaton::layer(self)
+	}
+
+	fn render(&self, context: &External, out: &mut Layers) {
 		Automaton::render(self, context, out)
 	}
 
@@ -74,3 +258,262 @@ impl<T: Automaton> GameObject for T {
 		Automaton::cleanup(self)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	enum State {
+		Forage,
+		Search,
+		Carry,
+		Rest,
+	}
+
+	impl MachineState for State {
+		fn parent(&self) -> Option<Self> {
+			match self {
+				State::Search | State::Carry => Some(State::Forage),
+				_ => None,
+			}
+		}
+
+		fn initial(&self) -> Option<Self> {
+			match self {
+				State::Forage => Some(State::Search),
+				_ => None,
+			}
+		}
+	}
+
+	impl StateGraph for State {
+		fn states() -> Vec<Self> {
+			use State::*;
+			vec![Forage, Search, Carry, Rest]
+		}
+
+		fn name(&self) -> &'static str {
+			match self {
+				State::Forage => "Forage",
+				State::Search => "Search",
+				State::Carry => "Carry",
+				State::Rest => "Rest",
+			}
+		}
+
+		fn transitions() -> Vec<(Self, Self)> {
+			use State::*;
+			vec![(Search, Carry), (Carry, Rest), (Rest, Search)]
+		}
+	}
+
+	#[derive(Debug, PartialEq)]
+	enum Event {
+		Exit(State),
+		Enter(State),
+		Transition(State, State),
+	}
+
+	struct Agent {
+		machine: Machine<State>,
+		events: Vec<Event>,
+		timeouts: Vec<(State, f32, State)>,
+	}
+
+	impl Agent {
+		fn new(external: &External) -> Self {
+			Self {
+				machine: Machine::new(State::Forage, external.now),
+				events: vec![],
+				timeouts: vec![],
+			}
+		}
+
+		fn events(&mut self) -> Vec<Event> {
+			std::mem::take(&mut self.events)
+		}
+	}
+
+	impl Automaton for Agent {
+		type Scene = ();
+		type Action = ();
+		type State = State;
+
+		fn machine(&self) -> &Machine<State> {
+			&self.machine
+		}
+
+		fn machine_mut(&mut self) -> &mut Machine<State> {
+			&mut self.machine
+		}
+
+		fn transition(&self, _: State, _: &External) -> Option<State> {
+			None
+		}
+
+		fn timeout(&self, state: State) -> Option<(f32, State)> {
+			self.timeouts
+				.iter()
+				.find(|&&(s, _, _)| s == state)
+				.map(|&(_, limit, next)| (limit, next))
+		}
+
+		fn enter(&mut self, state: State, _: State) {
+			self.events.push(Event::Enter(state));
+		}
+
+		fn exit(&mut self, state: State, _: State) {
+			self.events.push(Event::Exit(state));
+		}
+
+		fn on_transition(&mut self, from: State, to: State) {
+			self.events.push(Event::Transition(from, to));
+		}
+	}
+
+	fn external() -> External {
+		External::new(Default::default(), Default::default(), (1280, 720))
+	}
+
+	//Only the states below the one shared by both paths are left and
+	//entered, innermost first on the way out and outermost first on the
+	//way in.
+	#[test]
+	fn exits_and_enters_below_shared_state() {
+		use {Event::*, State::*};
+
+		let external = external();
+		let mut agent = Agent::new(&external);
+		assert_eq!(agent.state(), Search);
+		assert!(agent.machine().is_in(Forage));
+
+		agent.transition_to(Carry, external.now);
+		assert_eq!(
+			agent.events(),
+			[Exit(Search), Enter(Carry), Transition(Search, Carry)]
+		);
+
+		agent.transition_to(Rest, external.now);
+		assert_eq!(
+			agent.events(),
+			[
+				Exit(Carry),
+				Exit(Forage),
+				Enter(Rest),
+				Transition(Carry, Rest)
+			]
+		);
+
+		agent.transition_to(Forage, external.now);
+		assert_eq!(
+			agent.events(),
+			[
+				Exit(Rest),
+				Enter(Forage),
+				Enter(Search),
+				Transition(Rest, Search)
+			]
+		);
+	}
+
+	#[test]
+	fn records_transitions_in_log() {
+		use State::*;
+		static LOG: TransitionLog<State> = TransitionLog::new();
+
+		let external = external();
+		let mut agent = Agent::new(&external);
+		agent.machine = agent.machine.logged(&LOG);
+
+		agent.transition_to(Carry, external.now);
+		agent.transition_to(Rest, external.now);
+		agent.transition_to(Rest, external.now);
+
+		assert_eq!(LOG.count(Search, Carry), 1);
+		assert_eq!(LOG.count(Carry, Rest), 1);
+		assert_eq!(LOG.count(Rest, Rest), 1);
+		assert_eq!(LOG.count(Search, Rest), 0);
+
+		let dot = LOG.to_dot("Agent");
+		assert!(
+			dot.contains("\t\"Search\" -> \"Carry\" [label=\"1\", penwidth=2.33, style=solid];")
+		);
+		assert!(dot.contains("\t\"Rest\" -> \"Rest\" [label=\"1\", penwidth=2.33, style=dashed];"));
+	}
+
+	//Moving to the current state, or to one of its ancestors, leaves and
+	//re-enters it and restarts its clock.
+	#[test]
+	fn reenters_on_self_transition() {
+		use {Event::*, State::*};
+
+		let mut external = external();
+		let mut agent = Agent::new(&external);
+
+		external.now += Duration::from_secs(2);
+		agent.transition_to(Search, external.now);
+		assert_eq!(
+			agent.events(),
+			[Exit(Search), Enter(Search), Transition(Search, Search)]
+		);
+		assert_eq!(agent.elapsed(&external), 0.);
+		assert_eq!(agent.machine().elapsed_in(Forage, external.now), Some(2.));
+
+		agent.transition_to(Forage, external.now);
+		assert_eq!(
+			agent.events(),
+			[
+				Exit(Search),
+				Exit(Forage),
+				Enter(Forage),
+				Enter(Search),
+				Transition(Search, Search)
+			]
+		);
+		assert_eq!(agent.machine().elapsed_in(Forage, external.now), Some(0.));
+	}
+
+	//A timeout back into the same state fires once per period, rather
+	//than on every update once first reached.
+	#[test]
+	fn timeouts_restart_on_reentry() {
+		use {Event::*, State::*};
+
+		let mut external = external();
+		let messenger = Messenger::new();
+		let mut agent = Agent::new(&external);
+		agent.timeouts = vec![(Search, 1., Search), (Forage, 2.5, Rest)];
+
+		let mut step = |agent: &mut Agent, secs: f32| {
+			external.now += Duration::from_secs_f32(secs);
+			GameObject::update(agent, &external, &messenger);
+			agent.events()
+		};
+
+		assert_eq!(step(&mut agent, 0.5), []);
+		assert_eq!(
+			step(&mut agent, 0.75),
+			[Exit(Search), Enter(Search), Transition(Search, Search)]
+		);
+		assert_eq!(step(&mut agent, 0.5), []);
+		assert_eq!(
+			step(&mut agent, 0.75),
+			[Exit(Search), Enter(Search), Transition(Search, Search)]
+		);
+
+		//the parent's clock was never restarted, so its timeout is reached
+		//from within the child
+		assert_eq!(
+			step(&mut agent, 0.5),
+			[
+				Exit(Search),
+				Exit(Forage),
+				Enter(Rest),
+				Transition(Search, Rest)
+			]
+		);
+		assert_eq!(step(&mut agent, 10.), []);
+	}
+}
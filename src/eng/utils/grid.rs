@@ -30,27 +30,31 @@ impl<T: Griddable> Grid<T> {
 			elems: FreeList::new(),
 		}
 	}
+}
+
+impl<T: Griddable> SpatialIndex for Grid<T> {
+	type Item = T;
 
-	pub fn count(&self) -> usize {
+	fn count(&self) -> usize {
 		self.elems.count()
 	}
 
-	pub fn insert(&mut self, item: T) {
-		let cell = Self::grid_cell(self.scale, item.pos());
+	fn insert(&mut self, item: T) {
+		let cell = grid_cell(self.scale, item.pos());
 		let index = self.elems.insert(item);
 		self.grid.entry(cell).or_insert(vec![]).push(index);
 	}
 
-	pub fn get(&self, pos: (f32, f32)) -> Option<&T> {
-		let cell = self.grid.get(&Self::grid_cell(self.scale, pos));
+	fn get(&self, pos: (f32, f32)) -> Option<&T> {
+		let cell = self.grid.get(&grid_cell(self.scale, pos));
 		cell.and_then(|v| {
 			v.iter()
 				.find_map(|&index| Some(&self.elems[index]).filter(|e| e.pos() == pos))
 		})
 	}
 
-	pub fn remove(&mut self, pos: (f32, f32)) -> Option<T> {
-		let cell = self.grid.get_mut(&Self::grid_cell(self.scale, pos));
+	fn remove(&mut self, pos: (f32, f32)) -> Option<T> {
+		let cell = self.grid.get_mut(&grid_cell(self.scale, pos));
 
 		if let Some(v) = cell {
 			if let Some((cell_i, &elem_i)) = v
@@ -68,35 +72,9 @@ impl<T: Griddable> Grid<T> {
 		}
 	}
 
-	pub fn nearest_by<P>(&self, pos: (f32, f32), radius: f32, mut predicate: P) -> Option<(f32, &T)>
-	where
-		P: FnMut(f32, &T) -> Option<f32>,
-	{
-		self.query_with_dist(pos, radius)
-			.filter_map(|(d, t)| predicate(d, t).zip(Some(t)))
-			.min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())
-	}
-
-	pub fn nearest(&self, pos: (f32, f32), radius: f32) -> Option<&T> {
-		self.nearest_dist(pos, radius).map(|d| d.1)
-	}
-
-	pub fn nearest_dist(&self, pos: (f32, f32), radius: f32) -> Option<(f32, &T)> {
-		self.query_with_dist(pos, radius)
-			.min_by(|(d1, _), (d2, _)| d1.partial_cmp(d2).unwrap())
-	}
-
-	pub fn query_at(&self, pos: (f32, f32), radius: f32) -> impl Iterator<Item = &T> {
-		self.query_with_dist(pos, radius).map(|(_, item)| item)
-	}
-
-	pub fn query_with_dist(
-		&self,
-		(x, y): (f32, f32),
-		radius: f32,
-	) -> impl Iterator<Item = (f32, &T)> {
-		let (hi_x, hi_y) = Self::grid_cell(self.scale, (x + radius, y + radius));
-		let (lo_x, lo_y) = Self::grid_cell(self.scale, (x - radius, y - radius));
+	fn query_with_dist(&self, (x, y): (f32, f32), radius: f32) -> impl Iterator<Item = (f32, &T)> {
+		let (hi_x, hi_y) = grid_cell(self.scale, (x + radius, y + radius));
+		let (lo_x, lo_y) = grid_cell(self.scale, (x - radius, y - radius));
 
 		(lo_x..=hi_x)
 			.flat_map(move |i| (lo_y..=hi_y).map(move |j| (i, j)))
@@ -109,35 +87,7 @@ impl<T: Griddable> Grid<T> {
 			.filter(move |(d, i)| *d <= radius && i.alive())
 	}
 
-	//pairs not guaranteed to come out in any particular order.
-	//all pairs are unordered and distinctly located.
-	pub fn nearby_pairs(&self, distance: f32) -> impl Iterator<Item = (&T, &T)> {
-		let radius = (distance / self.scale).ceil() as i32;
-
-		let is_nearby =
-			move |a: &T, b: &T| -> bool { a.pos() < b.pos() && dist(a.pos(), b.pos()) <= distance };
-
-		self.grid
-			.iter()
-			.flat_map(move |(&(cx, cy), ids)| {
-				(cx..=cx + radius)
-					.flat_map(move |ix| (cy..=cy + radius).map(move |iy| (ix, iy)))
-					.filter_map(move |cell2| self.grid.get(&cell2))
-					.map(move |jds| (ids, jds))
-			})
-			.flat_map(move |(ids, jds)| {
-				let ielems = ids.iter().map(|&i| &self.elems[i]);
-				let jelems = jds.iter().map(|&j| &self.elems[j]);
-				ielems.flat_map(move |i| {
-					jelems
-						.clone()
-						.filter(move |j| is_nearby(i, j))
-						.map(move |j| (i, j))
-				})
-			})
-	}
-
-	pub fn retain<P: FnMut(&T) -> bool>(&mut self, mut predicate: P) {
+	fn retain<P: FnMut(&T) -> bool>(&mut self, mut predicate: P) {
 		for vec in self.grid.values_mut() {
 			let mut i = 0;
 			while i < vec.len() {
@@ -151,7 +101,7 @@ impl<T: Griddable> Grid<T> {
 		}
 	}
 
-	pub fn maintain(&mut self) {
+	fn maintain(&mut self) {
 		self.elems.sort_frees();
 
 		let mut moved = vec![];
@@ -161,7 +111,7 @@ impl<T: Griddable> Grid<T> {
 			while i < vec.len() {
 				let elem = &self.elems[vec[i]];
 				let alive = elem.alive();
-				let cell = Self::grid_cell(self.scale, elem.pos());
+				let cell = grid_cell(self.scale, elem.pos());
 
 				if !alive {
 					self.elems.remove(vec[i]);
@@ -180,18 +130,80 @@ impl<T: Griddable> Grid<T> {
 		}
 	}
 
-	pub fn cleanup(&mut self) {
+	fn sparse(&self) -> bool {
+		self.elems.sparse()
+	}
+
+	fn compact(&mut self) {
+		let remap = self.elems.compact();
+		for vec in self.grid.values_mut() {
+			remap_indices(vec, &remap);
+		}
+	}
+
+	fn cleanup(&mut self) {
 		self.grid.retain(|_, v| !v.is_empty());
 	}
 
-	pub fn iter(&self) -> impl Iterator<Item = &T> {
+	fn cells(&self) -> Vec<Cell> {
+		self.grid
+			.iter()
+			.map(|(&cell, v)| Cell::square(self.scale, cell, v.len()))
+			.collect()
+	}
+
+	fn iter(&self) -> impl Iterator<Item = &T> {
 		self.elems.iter().filter(|e| e.alive())
 	}
 
-	pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+	fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
 		self.elems.iter_mut().filter(|e| e.alive())
 	}
 
+	fn par_iter(&self) -> impl ParallelIterator<Item = &T>
+	where
+		T: Send + Sync,
+	{
+		self.elems.par_iter().filter(|e| e.alive())
+	}
+
+	fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T>
+	where
+		T: Send + Sync,
+	{
+		self.elems.par_iter_mut().filter(|e| e.alive())
+	}
+}
+
+impl<T: Griddable> Grid<T> {
+	//pairs not guaranteed to come out in any particular order.
+	//all pairs are unordered and distinctly located.
+	pub fn nearby_pairs(&self, distance: f32) -> impl Iterator<Item = (&T, &T)> {
+		let radius = (distance / self.scale).ceil() as i32;
+
+		let is_nearby =
+			move |a: &T, b: &T| -> bool { a.pos() < b.pos() && dist(a.pos(), b.pos()) <= distance };
+
+		self.grid
+			.iter()
+			.flat_map(move |(&(cx, cy), ids)| {
+				(cx..=cx + radius)
+					.flat_map(move |ix| (cy..=cy + radius).map(move |iy| (ix, iy)))
+					.filter_map(move |cell2| self.grid.get(&cell2))
+					.map(move |jds| (ids, jds))
+			})
+			.flat_map(move |(ids, jds)| {
+				let ielems = ids.iter().map(|&i| &self.elems[i]);
+				let jelems = jds.iter().map(|&j| &self.elems[j]);
+				ielems.flat_map(move |i| {
+					jelems
+						.clone()
+						.filter(move |j| is_nearby(i, j))
+						.map(move |j| (i, j))
+				})
+			})
+	}
+
 	pub fn from_iter<I: Iterator<Item = T>>(scale: f32, iter: I) -> Self {
 		let mut grid = Grid::new(scale);
 		for i in iter {
@@ -200,11 +212,6 @@ impl<T: Griddable> Grid<T> {
 		grid
 	}
 
-	fn grid_cell(scale: f32, (x, y): (f32, f32)) -> (i32, i32) {
-		debug_assert!(!x.is_nan() && !y.is_nan());
-		((x / scale).floor() as i32, (y / scale).floor() as i32)
-	}
-
 	pub fn dbg_analytics(&self) {
 		let cells = self.grid.len();
 		let len = self.elems.count();
@@ -234,7 +241,7 @@ impl<T: Griddable + Send + Sync> Grid<T> {
 					let lock = elems.read().unwrap();
 					let elem = &lock[vec[i]];
 					let alive = elem.alive();
-					let cell = Self::grid_cell(self.scale, elem.pos());
+					let cell = grid_cell(self.scale, elem.pos());
 					std::mem::drop(lock);
 
 					if !alive {
@@ -254,12 +261,103 @@ impl<T: Griddable + Send + Sync> Grid<T> {
 			self.grid.entry(cell).or_insert(vec![]).push(index);
 		}
 	}
+}
+
+//Only the elements are stored, since the buckets can be rebuilt from
+//their positions on load.
+#[cfg(feature = "serialize")]
+mod serialize {
+	use super::*;
+	use serde::*;
+
+	#[derive(Serialize)]
+	struct GridRef<'a, T: Serialize> {
+		scale: f32,
+		#[serde(serialize_with = "serialize_elems")]
+		elems: &'a FreeList<T>,
+	}
+
+	fn serialize_elems<T: Serialize, S: Serializer>(
+		elems: &&FreeList<T>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		compact::serialize(elems, serializer)
+	}
+
+	#[derive(Deserialize)]
+	#[serde(bound = "T: Deserialize<'de>")]
+	struct GridData<T> {
+		scale: f32,
+		#[serde(with = "compact")]
+		elems: FreeList<T>,
+	}
+
+	impl<T: Griddable + Serialize> Serialize for Grid<T> {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			GridRef {
+				scale: self.scale,
+				elems: &self.elems,
+			}
+			.serialize(serializer)
+		}
+	}
 
-	pub fn par_iter(&self) -> impl ParallelIterator<Item = &T> {
-		self.elems.par_iter()
+	impl<'de, T: Griddable + Deserialize<'de>> Deserialize<'de> for Grid<T> {
+		fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+			let GridData::<T> { scale, elems } = GridData::deserialize(deserializer)?;
+
+			let mut grid = Grid {
+				scale,
+				grid: Default::default(),
+				elems,
+			};
+
+			for index in 0..grid.elems.slot_count() {
+				let cell = grid_cell(scale, grid.elems[index].pos());
+				grid.grid.entry(cell).or_insert(vec![]).push(index);
+			}
+
+			Ok(grid)
+		}
+	}
+}
+
+#[cfg(all(test, feature = "serialize"))]
+mod serialize_tests {
+	use super::*;
+
+	#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+	struct Point((f32, f32));
+
+	impl Griddable for Point {
+		fn pos(&self) -> (f32, f32) {
+			self.0
+		}
 	}
 
-	pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T> {
-		self.elems.par_iter_mut()
+	fn sorted<'a>(found: impl Iterator<Item = &'a Point>) -> Vec<(f32, f32)> {
+		let mut found: Vec<_> = found.map(|p| p.0).collect();
+		found.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		found
+	}
+
+	#[test]
+	fn round_trip_rebuilds_the_buckets() {
+		let mut grid = Grid::new(4.);
+		grid.extend((0..40).map(|i| Point(((i * 7 % 41) as f32 - 20., (i * 3) as f32 - 60.))));
+		grid.remove((-20., -60.));
+
+		let text = ron::to_string(&grid).unwrap();
+		let back: Grid<Point> = ron::from_str(&text).unwrap();
+		assert_eq!(back.count(), grid.count());
+
+		for (centre, radius) in [((0., -30.), 10.), ((-15., -50.), 6.), ((5., 0.), 100.)] {
+			assert_eq!(
+				sorted(back.query_at(centre, radius)),
+				sorted(grid.query_at(centre, radius))
+			);
+		}
+		assert_eq!(back.get((-20., -60.)), None);
+		assert_eq!(back.get((-13., -57.)), Some(&Point((-13., -57.))));
 	}
 }
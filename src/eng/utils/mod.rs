@@ -1,18 +1,30 @@
 use super::*;
 use cgmath::*;
 
-pub mod ui;
 mod animation;
+mod behavior;
+mod density;
+mod dot;
 mod freelist;
 mod fsm;
 mod grid;
 mod relaxed;
+mod spatial;
+mod tween;
+pub mod ui;
+mod utility;
 
-pub use animation::Animation;
+pub use animation::{ease, Animation, Curve};
+pub use behavior::{Behavior, Edge, Senses, Status};
+pub use density::Density;
+pub use dot::{StateGraph, TransitionLog};
 pub use freelist::*;
-pub use fsm::Automaton;
+pub use fsm::{Automaton, Machine, MachineState};
 pub use grid::*;
 pub use relaxed::*;
+pub use spatial::*;
+pub use tween::*;
+pub use utility::*;
 
 pub fn unit_in_dir(deg: f32) -> Vector2<f32> {
 	vec2(deg.sin(), deg.cos())
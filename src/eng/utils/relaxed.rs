@@ -8,6 +8,14 @@ use std::time::Instant;
 pub trait Relax: GameObject {
 	//Number of updates per second
 	fn plan_frequency(&self) -> f32;
+	//Where the object is, so that it can plan less often while out of view.
+	fn focus(&self) -> Option<Vector2<f32>> {
+		None
+	}
+	//Idle objects plan less often.
+	fn idle(&self) -> bool {
+		false
+	}
 	fn must_plan(&self, _scene: &Self::Scene, _external: &External) -> bool {
 		false
 	}
@@ -19,13 +27,39 @@ pub trait Relax: GameObject {
 pub struct Relaxed<T> {
 	last_plan: Instant,
 	now: Instant,
+	//see Scheduler, given on the first update
+	slot: Option<usize>,
 	inner: T,
 }
 
 impl<T: Relax> Relaxed<T> {
+	const MIN_DETAIL: f32 = 0.05;
+	const IDLE_DETAIL: f32 = 0.25;
+
 	pub fn ready(&self) -> bool {
 		self.last_plan == self.now
 	}
+
+	//Proportion of the planning frequency to actually plan at. Drops
+	//with the square of the distance from the view, in view sizes.
+	pub fn detail(&self, external: &External) -> f32 {
+		let mut detail = 1.;
+
+		if let Some(focus) = self.inner.focus() {
+			if !external.point_in_view(focus) {
+				let half = external.view_dims() / 2.;
+				let diff = focus - external.camera.pos;
+				let dist = (diff.x.abs() / half.x).max(diff.y.abs() / half.y);
+				detail = dist.powi(-2).max(Self::MIN_DETAIL);
+			}
+		}
+
+		if self.inner.idle() {
+			detail *= Self::IDLE_DETAIL;
+		}
+
+		detail
+	}
 }
 
 impl<T: Relax> GameObject for Relaxed<T> {
@@ -43,15 +77,35 @@ impl<T: Relax> GameObject for Relaxed<T> {
 		self.now = external.now;
 
 		let elapsed = self.now.duration_since(self.last_plan).as_secs_f32();
-		let period = 1. / self.inner.plan_frequency();
-		if elapsed > period {
+		let period = 1. / (self.inner.plan_frequency() * self.detail(external));
+
+		//Ticks vary in length, so an object whose bucket keeps being
+		//skipped plans anyway once it is well overdue.
+		let ticks = (period / external.delta.max(f32::EPSILON)).round() as usize;
+		let scheduler = &external.scheduler;
+		let slot = *self.slot.get_or_insert_with(|| scheduler.slot());
+		let due = scheduler.due(slot, ticks) || elapsed > 2. * period;
+
+		if elapsed > period / 2. && due && scheduler.try_plan() {
 			self.last_plan = self.now;
 		}
 
 		self.inner.update(external, messenger)
 	}
 
-	fn render(&self, external: &External, out: &mut Vec<Instance>) {
+	fn layer(&self) -> Layer {
+		self.inner.layer()
+	}
+
+	fn statics(&self) -> Option<Static> {
+		self.inner.statics()
+	}
+
+	fn render_static(&self, external: &External, out: &mut Vec<Instance>) {
+		self.inner.render_static(external, out)
+	}
+
+	fn render(&self, external: &External, out: &mut Layers) {
 		self.inner.render(external, out)
 	}
 
@@ -72,6 +126,7 @@ impl<T: Relax> From<T> for Relaxed<T> {
 		Self {
 			last_plan: now,
 			now,
+			slot: None,
 			inner: value,
 		}
 	}
@@ -99,3 +154,136 @@ impl<T: Relax + Griddable> Griddable for Relaxed<T> {
 		self.inner.alive()
 	}
 }
+
+//Instants can't be serialized, so the planning phase is stored as the
+//time elapsed since the last plan, and resumed relative to load time.
+#[cfg(feature = "serialize")]
+mod serialize {
+	use super::*;
+	use serde::*;
+	use std::time::Duration;
+
+	#[derive(Serialize)]
+	struct RelaxedRef<'a, T> {
+		since_plan: f32,
+		inner: &'a T,
+	}
+
+	#[derive(Deserialize)]
+	struct RelaxedData<T> {
+		since_plan: f32,
+		inner: T,
+	}
+
+	impl<T: Serialize> Serialize for Relaxed<T> {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			RelaxedRef {
+				since_plan: self.now.duration_since(self.last_plan).as_secs_f32(),
+				inner: &self.inner,
+			}
+			.serialize(serializer)
+		}
+	}
+
+	impl<'de, T: Deserialize<'de>> Deserialize<'de> for Relaxed<T> {
+		fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+			let RelaxedData { since_plan, inner } = RelaxedData::deserialize(deserializer)?;
+
+			let now = Instant::now();
+			let since_plan = Duration::try_from_secs_f32(since_plan).unwrap_or_default();
+
+			Ok(Self {
+				last_plan: now.checked_sub(since_plan).unwrap_or(now),
+				now,
+				slot: None,
+				inner,
+			})
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Object {
+		focus: Vector2<f32>,
+		idle: bool,
+	}
+
+	impl GameObject for Object {
+		type Scene = ();
+		type Action = ();
+	}
+
+	impl Relax for Object {
+		fn plan_frequency(&self) -> f32 {
+			60.
+		}
+
+		fn focus(&self) -> Option<Vector2<f32>> {
+			Some(self.focus)
+		}
+
+		fn idle(&self) -> bool {
+			self.idle
+		}
+	}
+
+	fn detail(focus: (f32, f32), idle: bool) -> f32 {
+		//a view 512 high and 1024 wide, centred on the origin
+		let external = External::new(Default::default(), Default::default(), (1024, 512));
+		Relaxed::from(Object {
+			focus: focus.into(),
+			idle,
+		})
+		.detail(&external)
+	}
+
+	#[test]
+	fn detail_drops_with_distance_from_view() {
+		assert_eq!(detail((0., 0.), false), 1.);
+		assert_eq!(detail((500., 250.), false), 1.);
+
+		//two and four half-views away
+		assert_eq!(detail((1024., 0.), false), 0.25);
+		assert_eq!(detail((0., -1024.), false), 0.0625);
+
+		assert_eq!(detail((1e6, 1e6), false), Relaxed::<Object>::MIN_DETAIL);
+	}
+
+	#[test]
+	fn idle_objects_plan_less() {
+		assert_eq!(detail((0., 0.), true), Relaxed::<Object>::IDLE_DETAIL);
+		assert_eq!(
+			detail((1024., 0.), true),
+			0.25 * Relaxed::<Object>::IDLE_DETAIL
+		);
+	}
+}
+
+#[cfg(all(test, feature = "serialize"))]
+mod serialize_tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn round_trips_the_time_since_planning() {
+		let now = Instant::now();
+		let relaxed = Relaxed {
+			last_plan: now - Duration::from_millis(1500),
+			now,
+			slot: Some(3),
+			inner: 7u32,
+		};
+
+		let text = ron::to_string(&relaxed).unwrap();
+		let back: Relaxed<u32> = ron::from_str(&text).unwrap();
+
+		let since_plan = back.now.duration_since(back.last_plan).as_secs_f32();
+		assert!((since_plan - 1.5).abs() < 1e-3);
+		assert_eq!(back.inner, 7);
+		//slots are handed out again by the scheduler
+		assert_eq!(back.slot, None);
+	}
+}
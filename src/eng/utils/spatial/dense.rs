@@ -0,0 +1,188 @@
+use super::*;
+
+//Flat array of cells covering a fixed region. Avoids hashing entirely,
+//at the cost of memory proportional to the area covered. Elements
+//outside the region are kept in the nearest edge cell.
+pub struct DenseGrid<T: Griddable> {
+	scale: f32,
+	lo: (i32, i32),
+	dims: (i32, i32),
+	cells: Vec<Vec<usize>>,
+	elems: FreeList<T>,
+}
+
+impl<T: Griddable> DenseGrid<T> {
+	pub fn new(lo: (f32, f32), hi: (f32, f32), scale: f32) -> Self {
+		let lo = grid_cell(scale, lo);
+		let hi = grid_cell(scale, hi);
+		let dims = (hi.0 - lo.0 + 1, hi.1 - lo.1 + 1);
+		debug_assert!(dims.0 > 0 && dims.1 > 0);
+
+		Self {
+			scale,
+			lo,
+			dims,
+			cells: vec![vec![]; (dims.0 * dims.1) as usize],
+			elems: FreeList::new(),
+		}
+	}
+
+	fn clamped_cell(&self, pos: (f32, f32)) -> (i32, i32) {
+		let (x, y) = grid_cell(self.scale, pos);
+		(
+			(x - self.lo.0).clamp(0, self.dims.0 - 1),
+			(y - self.lo.1).clamp(0, self.dims.1 - 1),
+		)
+	}
+
+	fn cell_index(&self, (x, y): (i32, i32)) -> usize {
+		(y * self.dims.0 + x) as usize
+	}
+
+	fn cell_of(&self, pos: (f32, f32)) -> usize {
+		self.cell_index(self.clamped_cell(pos))
+	}
+}
+
+impl<T: Griddable> SpatialIndex for DenseGrid<T> {
+	type Item = T;
+
+	fn count(&self) -> usize {
+		self.elems.count()
+	}
+
+	fn insert(&mut self, item: T) {
+		let cell = self.cell_of(item.pos());
+		let index = self.elems.insert(item);
+		self.cells[cell].push(index);
+	}
+
+	fn get(&self, pos: (f32, f32)) -> Option<&T> {
+		self.cells[self.cell_of(pos)]
+			.iter()
+			.find_map(|&index| Some(&self.elems[index]).filter(|e| e.pos() == pos))
+	}
+
+	fn remove(&mut self, pos: (f32, f32)) -> Option<T> {
+		let cell = self.cell_of(pos);
+		let v = &mut self.cells[cell];
+
+		let (cell_i, &elem_i) = v
+			.iter()
+			.enumerate()
+			.find(|(_, &index)| self.elems[index].pos() == pos)?;
+
+		v.swap_remove(cell_i);
+		self.elems.remove(elem_i)
+	}
+
+	fn query_with_dist(&self, (x, y): (f32, f32), radius: f32) -> impl Iterator<Item = (f32, &T)> {
+		let (hi_x, hi_y) = self.clamped_cell((x + radius, y + radius));
+		let (lo_x, lo_y) = self.clamped_cell((x - radius, y - radius));
+
+		(lo_y..=hi_y)
+			.flat_map(move |j| (lo_x..=hi_x).map(move |i| (i, j)))
+			.flat_map(|cell| &self.cells[self.cell_index(cell)])
+			.map(move |&index| {
+				let item = &self.elems[index];
+				(dist(item.pos(), (x, y)), item)
+			})
+			.filter(move |(d, i)| *d <= radius && i.alive())
+	}
+
+	fn retain<P: FnMut(&T) -> bool>(&mut self, mut predicate: P) {
+		for vec in &mut self.cells {
+			let mut i = 0;
+			while i < vec.len() {
+				if !predicate(&self.elems[vec[i]]) {
+					self.elems.remove(vec[i]);
+					vec.swap_remove(i);
+				} else {
+					i += 1;
+				}
+			}
+		}
+	}
+
+	fn maintain(&mut self) {
+		self.elems.sort_frees();
+
+		let mut moved = vec![];
+		let mut cells = std::mem::take(&mut self.cells);
+
+		for (bucket, vec) in cells.iter_mut().enumerate() {
+			let mut i = 0;
+			while i < vec.len() {
+				let elem = &self.elems[vec[i]];
+				let alive = elem.alive();
+				let cell = self.cell_of(elem.pos());
+
+				if !alive {
+					self.elems.remove(vec[i]);
+					vec.swap_remove(i);
+				} else if cell != bucket {
+					moved.push((cell, vec[i]));
+					vec.swap_remove(i);
+				} else {
+					i += 1;
+				}
+			}
+		}
+
+		for (cell, index) in moved {
+			cells[cell].push(index);
+		}
+
+		self.cells = cells;
+	}
+
+	fn sparse(&self) -> bool {
+		self.elems.sparse()
+	}
+
+	fn compact(&mut self) {
+		let remap = self.elems.compact();
+		for vec in &mut self.cells {
+			remap_indices(vec, &remap);
+		}
+	}
+
+	fn cleanup(&mut self) {
+		for cell in &mut self.cells {
+			cell.shrink_to_fit();
+		}
+	}
+
+	fn cells(&self) -> Vec<Cell> {
+		self.cells
+			.iter()
+			.enumerate()
+			.map(|(i, v)| {
+				let (x, y) = (i as i32 % self.dims.0, i as i32 / self.dims.0);
+				Cell::square(self.scale, (x + self.lo.0, y + self.lo.1), v.len())
+			})
+			.collect()
+	}
+
+	fn iter(&self) -> impl Iterator<Item = &T> {
+		self.elems.iter().filter(|e| e.alive())
+	}
+
+	fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+		self.elems.iter_mut().filter(|e| e.alive())
+	}
+
+	fn par_iter(&self) -> impl ParallelIterator<Item = &T>
+	where
+		T: Send + Sync,
+	{
+		self.elems.par_iter().filter(|e| e.alive())
+	}
+
+	fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T>
+	where
+		T: Send + Sync,
+	{
+		self.elems.par_iter_mut().filter(|e| e.alive())
+	}
+}
@@ -0,0 +1,263 @@
+//Common interface for containers which can be queried by position.
+//Every backend stores its elements in a FreeList and keeps some
+//structure of indices into it, so that different distributions
+//(uniform, clustered, bounded) can pick the index that suits them.
+
+mod dense;
+mod morton;
+mod quadtree;
+
+pub use dense::*;
+pub use morton::*;
+pub use quadtree::*;
+
+use super::*;
+use rayon::prelude::*;
+
+pub trait SpatialIndex {
+	type Item: Griddable;
+
+	fn count(&self) -> usize;
+
+	fn insert(&mut self, item: Self::Item);
+
+	fn get(&self, pos: (f32, f32)) -> Option<&Self::Item>;
+
+	fn remove(&mut self, pos: (f32, f32)) -> Option<Self::Item>;
+
+	fn query_with_dist(
+		&self,
+		pos: (f32, f32),
+		radius: f32,
+	) -> impl Iterator<Item = (f32, &Self::Item)>;
+
+	fn retain<P: FnMut(&Self::Item) -> bool>(&mut self, predicate: P);
+
+	//Removes dead elements and moves the rest to wherever their
+	//current position belongs.
+	fn maintain(&mut self);
+
+	//Whether most of the memory held is for removed elements, which
+	//`compact` would release.
+	fn sparse(&self) -> bool;
+
+	//Releases the memory held by removed elements. Never done
+	//automatically, since it touches every element.
+	fn compact(&mut self);
+
+	fn cleanup(&mut self) {}
+
+	//How the elements are divided between regions, for debugging.
+	fn cells(&self) -> Vec<Cell>;
+
+	fn iter(&self) -> impl Iterator<Item = &Self::Item>;
+
+	fn iter_mut(&mut self) -> impl Iterator<Item = &mut Self::Item>;
+
+	fn par_iter(&self) -> impl ParallelIterator<Item = &Self::Item>
+	where
+		Self::Item: Send + Sync;
+
+	fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut Self::Item>
+	where
+		Self::Item: Send + Sync;
+
+	fn extend<I: IntoIterator<Item = Self::Item>>(&mut self, iter: I)
+	where
+		Self: Sized,
+	{
+		for item in iter {
+			self.insert(item);
+		}
+	}
+
+	fn query_at(&self, pos: (f32, f32), radius: f32) -> impl Iterator<Item = &Self::Item> {
+		self.query_with_dist(pos, radius).map(|(_, item)| item)
+	}
+
+	fn nearest_by<P>(
+		&self,
+		pos: (f32, f32),
+		radius: f32,
+		mut predicate: P,
+	) -> Option<(f32, &Self::Item)>
+	where
+		P: FnMut(f32, &Self::Item) -> Option<f32>,
+	{
+		self.query_with_dist(pos, radius)
+			.filter_map(|(d, t)| predicate(d, t).zip(Some(t)))
+			.min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())
+	}
+
+	fn nearest(&self, pos: (f32, f32), radius: f32) -> Option<&Self::Item> {
+		self.nearest_dist(pos, radius).map(|d| d.1)
+	}
+
+	fn nearest_dist(&self, pos: (f32, f32), radius: f32) -> Option<(f32, &Self::Item)> {
+		self.query_with_dist(pos, radius)
+			.min_by(|(d1, _), (d2, _)| d1.partial_cmp(d2).unwrap())
+	}
+
+	//Number of elements falling in each pixel of the given region.
+	fn density(&self, lo: (f32, f32), hi: (f32, f32), resolution: (u32, u32)) -> Density {
+		self.weighted_density(lo, hi, resolution, |_| 1.)
+	}
+
+	fn weighted_density<W>(
+		&self,
+		lo: (f32, f32),
+		hi: (f32, f32),
+		resolution: (u32, u32),
+		mut weight: W,
+	) -> Density
+	where
+		W: FnMut(&Self::Item) -> f32,
+	{
+		let mut density = Density::new(lo, hi, resolution);
+
+		let center = ((lo.0 + hi.0) / 2., (lo.1 + hi.1) / 2.);
+		let radius = dist(lo, hi) / 2.;
+
+		for item in self.query_at(center, radius) {
+			density.splat(item.pos(), weight(item));
+		}
+
+		density
+	}
+}
+
+//Rewrites indices using the table returned by `FreeList::compact`.
+pub(super) fn remap_indices<'a, I>(indices: I, remap: &[Option<usize>])
+where
+	I: IntoIterator<Item = &'a mut usize>,
+{
+	for index in indices {
+		*index = remap[*index].expect("Index refers to a removed element.");
+	}
+}
+
+//A region of a spatial index, and how many elements are kept in it.
+#[derive(Clone, Copy, Debug)]
+pub struct Cell {
+	pub lo: (f32, f32),
+	pub hi: (f32, f32),
+	pub count: usize,
+}
+
+impl Cell {
+	pub(super) fn square(scale: f32, (x, y): (i32, i32), count: usize) -> Self {
+		Self {
+			lo: (x as f32 * scale, y as f32 * scale),
+			hi: ((x + 1) as f32 * scale, (y + 1) as f32 * scale),
+			count,
+		}
+	}
+}
+
+pub(super) fn grid_cell(scale: f32, (x, y): (f32, f32)) -> (i32, i32) {
+	debug_assert!(!x.is_nan() && !y.is_nan());
+	((x / scale).floor() as i32, (y / scale).floor() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rand::{rngs::StdRng, Rng, SeedableRng};
+
+	#[derive(Clone, Copy, Debug, PartialEq)]
+	struct Point((f32, f32));
+
+	impl Griddable for Point {
+		fn pos(&self) -> (f32, f32) {
+			self.0
+		}
+	}
+
+	fn points(n: usize) -> Vec<Point> {
+		let mut rng = StdRng::seed_from_u64(26);
+		(0..n)
+			.map(|_| Point((rng.gen_range(-500. ..500.), rng.gen_range(-500. ..500.))))
+			.collect()
+	}
+
+	fn sorted(mut found: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+		found.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		found
+	}
+
+	//Queries around and across the origin, where the cell coordinates
+	//change sign, must find exactly what a linear scan does.
+	fn matches_linear_scan<S: SpatialIndex<Item = Point>>(mut index: S) {
+		let points = points(2000);
+		index.extend(points.iter().copied());
+		assert_eq!(index.count(), points.len());
+
+		let mut rng = StdRng::seed_from_u64(27);
+		let centres = [(0., 0.), (-10., 5.), (300., -20.)]
+			.into_iter()
+			.chain((0..50).map(|_| (rng.gen_range(-600. ..600.), rng.gen_range(-600. ..600.))));
+
+		for centre in centres {
+			for radius in [1., 25., 120.] {
+				let expected = points
+					.iter()
+					.filter(|p| dist(p.0, centre) <= radius)
+					.map(|p| p.0)
+					.collect();
+				let found = index.query_at(centre, radius).map(|p| p.0).collect();
+				assert_eq!(sorted(found), sorted(expected), "{:?} {}", centre, radius);
+			}
+		}
+	}
+
+	//Elements can be found, removed, and the rest survive compaction,
+	//which only happens when asked for.
+	fn removes_and_compacts<S: SpatialIndex<Item = Point>>(mut index: S) {
+		let points = points(500);
+		index.extend(points.iter().copied());
+
+		let (kept, removed): (Vec<&Point>, Vec<_>) = points.iter().partition(|p| p.0 .0 > 200.);
+		for p in &removed {
+			assert_eq!(index.get(p.0), Some(*p));
+			assert_eq!(index.remove(p.0), Some(**p));
+			assert_eq!(index.get(p.0), None);
+		}
+
+		assert!(index.sparse());
+		index.cleanup();
+		assert!(index.sparse());
+		index.compact();
+		assert!(!index.sparse());
+
+		assert_eq!(index.count(), kept.len());
+		for p in kept {
+			assert_eq!(index.get(p.0), Some(p));
+			assert!(index.query_at(p.0, 0.5).any(|q| q == p));
+		}
+	}
+
+	#[test]
+	fn grid() {
+		matches_linear_scan(Grid::new(32.));
+		removes_and_compacts(Grid::new(32.));
+	}
+
+	#[test]
+	fn dense_grid() {
+		//smaller than the points, to cover those kept in the edge cells
+		matches_linear_scan(DenseGrid::new((-400., -400.), (400., 400.), 32.));
+		removes_and_compacts(DenseGrid::new((-400., -400.), (400., 400.), 32.));
+	}
+
+	#[test]
+	fn morton_list() {
+		matches_linear_scan(MortonList::new(32.));
+		removes_and_compacts(MortonList::new(32.));
+	}
+
+	#[test]
+	fn quadtree() {
+		matches_linear_scan(QuadTree::new((-400., -400.), (400., 400.)));
+		removes_and_compacts(QuadTree::new((-400., -400.), (400., 400.)));
+	}
+}
@@ -0,0 +1,245 @@
+use super::*;
+
+//Elements sorted by the Morton (Z-order) code of their cell, so that
+//nearby elements sit close together in memory. Queries scan the range
+//of codes spanned by the query box, skipping ahead past runs of codes
+//which fall outside it.
+pub struct MortonList<T: Griddable> {
+	scale: f32,
+	keys: Vec<(u64, usize)>,
+	elems: FreeList<T>,
+}
+
+impl<T: Griddable> MortonList<T> {
+	pub fn new(scale: f32) -> Self {
+		Self {
+			scale,
+			keys: vec![],
+			elems: FreeList::new(),
+		}
+	}
+
+	fn key(&self, pos: (f32, f32)) -> u64 {
+		morton_code(grid_cell(self.scale, pos))
+	}
+
+	//range of `keys` which share the cell of `pos`
+	fn cell_range(&self, pos: (f32, f32)) -> std::ops::Range<usize> {
+		let key = self.key(pos);
+		let start = self.keys.partition_point(|&(k, _)| k < key);
+		let end = start + self.keys[start..].partition_point(|&(k, _)| k == key);
+		start..end
+	}
+}
+
+impl<T: Griddable> SpatialIndex for MortonList<T> {
+	type Item = T;
+
+	fn count(&self) -> usize {
+		self.elems.count()
+	}
+
+	fn insert(&mut self, item: T) {
+		let key = self.key(item.pos());
+		let index = self.elems.insert(item);
+		let at = self.keys.partition_point(|&(k, _)| k <= key);
+		self.keys.insert(at, (key, index));
+	}
+
+	fn get(&self, pos: (f32, f32)) -> Option<&T> {
+		self.keys[self.cell_range(pos)]
+			.iter()
+			.find_map(|&(_, index)| Some(&self.elems[index]).filter(|e| e.pos() == pos))
+	}
+
+	fn remove(&mut self, pos: (f32, f32)) -> Option<T> {
+		let range = self.cell_range(pos);
+		let key_i = range
+			.into_iter()
+			.find(|&i| self.elems[self.keys[i].1].pos() == pos)?;
+
+		let (_, elem_i) = self.keys.remove(key_i);
+		self.elems.remove(elem_i)
+	}
+
+	fn query_with_dist(&self, (x, y): (f32, f32), radius: f32) -> impl Iterator<Item = (f32, &T)> {
+		let lo = self.key((x - radius, y - radius));
+		let hi = self.key((x + radius, y + radius));
+		let mut i = self.keys.partition_point(|&(k, _)| k < lo);
+
+		std::iter::from_fn(move || loop {
+			let &(key, index) = self.keys.get(i).filter(|&&(k, _)| k <= hi)?;
+			if in_box(key, lo, hi) {
+				i += 1;
+				return Some(index);
+			}
+			let next = bigmin(key, lo, hi);
+			i += self.keys[i..].partition_point(|&(k, _)| k < next);
+		})
+		.map(move |index| {
+			let item = &self.elems[index];
+			(dist(item.pos(), (x, y)), item)
+		})
+		.filter(move |(d, i)| *d <= radius && i.alive())
+	}
+
+	fn retain<P: FnMut(&T) -> bool>(&mut self, mut predicate: P) {
+		let elems = &mut self.elems;
+		self.keys.retain(|&(_, index)| {
+			let keep = predicate(&elems[index]);
+			if !keep {
+				elems.remove(index);
+			}
+			keep
+		});
+	}
+
+	fn maintain(&mut self) {
+		self.elems.sort_frees();
+
+		let scale = self.scale;
+		let elems = &mut self.elems;
+		self.keys.retain_mut(|(key, index)| {
+			let elem = &elems[*index];
+			if elem.alive() {
+				*key = morton_code(grid_cell(scale, elem.pos()));
+				true
+			} else {
+				elems.remove(*index);
+				false
+			}
+		});
+
+		//mostly sorted already, since elements rarely change cells
+		self.keys.sort_by_key(|&(key, _)| key);
+	}
+
+	fn sparse(&self) -> bool {
+		self.elems.sparse()
+	}
+
+	fn compact(&mut self) {
+		let remap = self.elems.compact();
+		remap_indices(self.keys.iter_mut().map(|(_, index)| index), &remap);
+	}
+
+	fn cleanup(&mut self) {
+		self.keys.shrink_to_fit();
+	}
+
+	fn cells(&self) -> Vec<Cell> {
+		self.keys
+			.chunk_by(|a, b| a.0 == b.0)
+			.map(|run| {
+				let cell = grid_cell(self.scale, self.elems[run[0].1].pos());
+				Cell::square(self.scale, cell, run.len())
+			})
+			.collect()
+	}
+
+	fn iter(&self) -> impl Iterator<Item = &T> {
+		self.elems.iter().filter(|e| e.alive())
+	}
+
+	fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+		self.elems.iter_mut().filter(|e| e.alive())
+	}
+
+	fn par_iter(&self) -> impl ParallelIterator<Item = &T>
+	where
+		T: Send + Sync,
+	{
+		self.elems.par_iter().filter(|e| e.alive())
+	}
+
+	fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T>
+	where
+		T: Send + Sync,
+	{
+		self.elems.par_iter_mut().filter(|e| e.alive())
+	}
+}
+
+//Interleaves the bits of both coordinates. Flipping the sign bit keeps
+//the ordering of negative cells intact.
+fn morton_code((x, y): (i32, i32)) -> u64 {
+	fn spread(v: u32) -> u64 {
+		let mut v = v as u64;
+		v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+		v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+		v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+		v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+		v = (v | (v << 1)) & 0x5555_5555_5555_5555;
+		v
+	}
+
+	let flip = |c: i32| (c as u32) ^ 0x8000_0000;
+
+	spread(flip(x)) | (spread(flip(y)) << 1)
+}
+
+//bits of the code taken from x, the rest are from y
+const X_BITS: u64 = 0x5555_5555_5555_5555;
+
+//Whether the cell of `key` lies within the box with corners `lo` and `hi`.
+//Comparing the bits of one coordinate at a time compares that coordinate.
+fn in_box(key: u64, lo: u64, hi: u64) -> bool {
+	[X_BITS, X_BITS << 1]
+		.iter()
+		.all(|&dim| (lo & dim) <= (key & dim) && (key & dim) <= (hi & dim))
+}
+
+//The smallest code greater than `key` whose cell lies within the box, for
+//a key between the corners but outside the box (Tropf and Herzog's BIGMIN).
+fn bigmin(key: u64, mut lo: u64, mut hi: u64) -> u64 {
+	let mut bigmin = hi;
+
+	for bit in (0..64).rev() {
+		let mask = 1 << bit;
+		//lower bits of the same coordinate
+		let below = (X_BITS << (bit & 1)) & (mask - 1);
+		//sets the bit and clears those below it, or the other way around
+		let set = |v: u64| (v | mask) & !below;
+		let clear = |v: u64| (v & !mask) | below;
+
+		match (key & mask != 0, lo & mask != 0, hi & mask != 0) {
+			(false, false, true) => {
+				bigmin = set(lo);
+				hi = clear(hi);
+			}
+			(false, true, true) => return lo,
+			(true, false, false) => return bigmin,
+			(true, false, true) => lo = set(lo),
+			_ => {}
+		}
+	}
+
+	bigmin
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	//Every code skipped to is the next one within the box.
+	#[test]
+	fn bigmin_is_next_in_box() {
+		let (lo, hi) = (morton_code((-3, -2)), morton_code((2, 4)));
+		let codes: Vec<_> = (-8..8)
+			.flat_map(|x| (-8..8).map(move |y| morton_code((x, y))))
+			.filter(|&code| lo <= code && code <= hi)
+			.collect();
+
+		for &code in &codes {
+			if !in_box(code, lo, hi) {
+				let next = codes
+					.iter()
+					.copied()
+					.filter(|&c| c > code && in_box(c, lo, hi))
+					.min()
+					.unwrap();
+				assert_eq!(bigmin(code, lo, hi), next);
+			}
+		}
+	}
+}
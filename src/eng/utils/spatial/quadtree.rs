@@ -0,0 +1,341 @@
+use super::*;
+
+//Recursively subdivided region, suited to clustered elements such as
+//food patches, where a uniform grid would either waste cells or pack
+//too much into a few. Elements outside the root region are kept in a
+//separate list, which is checked by every query.
+pub struct QuadTree<T: Griddable> {
+	bounds: Bounds,
+	root: Node,
+	outside: Vec<usize>,
+	elems: FreeList<T>,
+}
+
+#[derive(Clone, Copy)]
+struct Bounds {
+	lo: (f32, f32),
+	hi: (f32, f32),
+}
+
+enum Node {
+	Leaf(Vec<usize>),
+	Branch(Box<[Node; 4]>),
+}
+
+impl<T: Griddable> QuadTree<T> {
+	const CAPACITY: usize = 16;
+	const MAX_DEPTH: u32 = 10;
+
+	pub fn new(lo: (f32, f32), hi: (f32, f32)) -> Self {
+		Self {
+			bounds: Bounds { lo, hi },
+			root: Node::Leaf(vec![]),
+			outside: vec![],
+			elems: FreeList::new(),
+		}
+	}
+
+	fn place(&mut self, index: usize) {
+		let pos = self.elems[index].pos();
+		if self.bounds.contains(pos) {
+			self.root.insert(self.bounds, 0, index, &self.elems);
+		} else {
+			self.outside.push(index);
+		}
+	}
+
+	//the list of indices which would hold an element at `pos`
+	fn bucket(&self, pos: (f32, f32)) -> &Vec<usize> {
+		if self.bounds.contains(pos) {
+			self.root.leaf(self.bounds, pos)
+		} else {
+			&self.outside
+		}
+	}
+
+	fn bucket_mut(&mut self, pos: (f32, f32)) -> &mut Vec<usize> {
+		if self.bounds.contains(pos) {
+			self.root.leaf_mut(self.bounds, pos)
+		} else {
+			&mut self.outside
+		}
+	}
+}
+
+impl<T: Griddable> SpatialIndex for QuadTree<T> {
+	type Item = T;
+
+	fn count(&self) -> usize {
+		self.elems.count()
+	}
+
+	fn insert(&mut self, item: T) {
+		let index = self.elems.insert(item);
+		self.place(index);
+	}
+
+	fn get(&self, pos: (f32, f32)) -> Option<&T> {
+		self.bucket(pos)
+			.iter()
+			.find_map(|&index| Some(&self.elems[index]).filter(|e| e.pos() == pos))
+	}
+
+	fn remove(&mut self, pos: (f32, f32)) -> Option<T> {
+		//temporarily take the bucket, since finding it borrows the tree
+		let mut v = std::mem::take(self.bucket_mut(pos));
+
+		let found = v
+			.iter()
+			.position(|&index| self.elems[index].pos() == pos)
+			.map(|cell_i| v.swap_remove(cell_i));
+
+		*self.bucket_mut(pos) = v;
+
+		found.and_then(|elem_i| self.elems.remove(elem_i))
+	}
+
+	fn query_with_dist(&self, (x, y): (f32, f32), radius: f32) -> impl Iterator<Item = (f32, &T)> {
+		let query = Bounds {
+			lo: (x - radius, y - radius),
+			hi: (x + radius, y + radius),
+		};
+
+		let mut found = vec![];
+		self.root.collect(self.bounds, query, &mut found);
+
+		found
+			.into_iter()
+			.chain(self.outside.iter().copied())
+			.map(move |index| {
+				let item = &self.elems[index];
+				(dist(item.pos(), (x, y)), item)
+			})
+			.filter(move |(d, i)| *d <= radius && i.alive())
+	}
+
+	fn retain<P: FnMut(&T) -> bool>(&mut self, mut predicate: P) {
+		let elems = &mut self.elems;
+		let mut keep = |index: &usize| {
+			let keep = predicate(&elems[*index]);
+			if !keep {
+				elems.remove(*index);
+			}
+			keep
+		};
+
+		self.root.retain(&mut keep);
+		self.outside.retain(keep);
+	}
+
+	//Elements may have moved anywhere, so the tree is simply rebuilt.
+	fn maintain(&mut self) {
+		self.elems.sort_frees();
+
+		let mut indices = std::mem::take(&mut self.outside);
+		self.root.drain_into(&mut indices);
+
+		for index in indices {
+			if self.elems[index].alive() {
+				self.place(index);
+			} else {
+				self.elems.remove(index);
+			}
+		}
+	}
+
+	fn sparse(&self) -> bool {
+		self.elems.sparse()
+	}
+
+	fn compact(&mut self) {
+		let remap = self.elems.compact();
+		self.root.remap(&remap);
+		remap_indices(&mut self.outside, &remap);
+	}
+
+	//Leaves of the tree. Elements outside it have no region to show.
+	fn cells(&self) -> Vec<Cell> {
+		let mut out = vec![];
+		self.root.cells(self.bounds, &mut out);
+		out
+	}
+
+	fn iter(&self) -> impl Iterator<Item = &T> {
+		self.elems.iter().filter(|e| e.alive())
+	}
+
+	fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+		self.elems.iter_mut().filter(|e| e.alive())
+	}
+
+	fn par_iter(&self) -> impl ParallelIterator<Item = &T>
+	where
+		T: Send + Sync,
+	{
+		self.elems.par_iter().filter(|e| e.alive())
+	}
+
+	fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T>
+	where
+		T: Send + Sync,
+	{
+		self.elems.par_iter_mut().filter(|e| e.alive())
+	}
+}
+
+impl Bounds {
+	fn contains(&self, (x, y): (f32, f32)) -> bool {
+		self.lo.0 <= x && x < self.hi.0 && self.lo.1 <= y && y < self.hi.1
+	}
+
+	fn overlaps(&self, other: &Bounds) -> bool {
+		self.lo.0 <= other.hi.0
+			&& other.lo.0 <= self.hi.0
+			&& self.lo.1 <= other.hi.1
+			&& other.lo.1 <= self.hi.1
+	}
+
+	fn center(&self) -> (f32, f32) {
+		((self.lo.0 + self.hi.0) / 2., (self.lo.1 + self.hi.1) / 2.)
+	}
+
+	//quadrants in the order [lower left, lower right, upper left, upper right]
+	fn quadrant(&self, q: usize) -> Bounds {
+		let (cx, cy) = self.center();
+		let (lo_x, hi_x) = if q & 1 == 0 {
+			(self.lo.0, cx)
+		} else {
+			(cx, self.hi.0)
+		};
+		let (lo_y, hi_y) = if q & 2 == 0 {
+			(self.lo.1, cy)
+		} else {
+			(cy, self.hi.1)
+		};
+		Bounds {
+			lo: (lo_x, lo_y),
+			hi: (hi_x, hi_y),
+		}
+	}
+
+	fn quadrant_of(&self, (x, y): (f32, f32)) -> usize {
+		let (cx, cy) = self.center();
+		(x >= cx) as usize | ((y >= cy) as usize) << 1
+	}
+}
+
+impl Node {
+	fn insert<T: Griddable>(
+		&mut self,
+		bounds: Bounds,
+		depth: u32,
+		index: usize,
+		elems: &FreeList<T>,
+	) {
+		match self {
+			Node::Branch(children) => {
+				let q = bounds.quadrant_of(elems[index].pos());
+				children[q].insert(bounds.quadrant(q), depth + 1, index, elems);
+			}
+			Node::Leaf(v)
+				if v.len() < QuadTree::<T>::CAPACITY || depth >= QuadTree::<T>::MAX_DEPTH =>
+			{
+				v.push(index);
+			}
+			Node::Leaf(v) => {
+				let old = std::mem::take(v);
+				*self = Node::Branch(Box::new([
+					Node::Leaf(vec![]),
+					Node::Leaf(vec![]),
+					Node::Leaf(vec![]),
+					Node::Leaf(vec![]),
+				]));
+				for i in old.into_iter().chain(Some(index)) {
+					self.insert(bounds, depth, i, elems);
+				}
+			}
+		}
+	}
+
+	fn leaf(&self, bounds: Bounds, pos: (f32, f32)) -> &Vec<usize> {
+		match self {
+			Node::Leaf(v) => v,
+			Node::Branch(children) => {
+				let q = bounds.quadrant_of(pos);
+				children[q].leaf(bounds.quadrant(q), pos)
+			}
+		}
+	}
+
+	fn leaf_mut(&mut self, bounds: Bounds, pos: (f32, f32)) -> &mut Vec<usize> {
+		match self {
+			Node::Leaf(v) => v,
+			Node::Branch(children) => {
+				let q = bounds.quadrant_of(pos);
+				children[q].leaf_mut(bounds.quadrant(q), pos)
+			}
+		}
+	}
+
+	fn cells(&self, bounds: Bounds, out: &mut Vec<Cell>) {
+		match self {
+			Node::Leaf(v) => out.push(Cell {
+				lo: bounds.lo,
+				hi: bounds.hi,
+				count: v.len(),
+			}),
+			Node::Branch(children) => {
+				for (q, child) in children.iter().enumerate() {
+					child.cells(bounds.quadrant(q), out);
+				}
+			}
+		}
+	}
+
+	fn collect(&self, bounds: Bounds, query: Bounds, out: &mut Vec<usize>) {
+		if !bounds.overlaps(&query) {
+			return;
+		}
+		match self {
+			Node::Leaf(v) => out.extend_from_slice(v),
+			Node::Branch(children) => {
+				for (q, child) in children.iter().enumerate() {
+					child.collect(bounds.quadrant(q), query, out);
+				}
+			}
+		}
+	}
+
+	fn retain<P: FnMut(&usize) -> bool>(&mut self, predicate: &mut P) {
+		match self {
+			Node::Leaf(v) => v.retain(predicate),
+			Node::Branch(children) => {
+				for child in children.iter_mut() {
+					child.retain(predicate);
+				}
+			}
+		}
+	}
+
+	fn remap(&mut self, remap: &[Option<usize>]) {
+		match self {
+			Node::Leaf(v) => remap_indices(v, remap),
+			Node::Branch(children) => {
+				for child in children.iter_mut() {
+					child.remap(remap);
+				}
+			}
+		}
+	}
+
+	fn drain_into(&mut self, out: &mut Vec<usize>) {
+		match std::mem::replace(self, Node::Leaf(vec![])) {
+			Node::Leaf(v) => out.extend(v),
+			Node::Branch(mut children) => {
+				for child in children.iter_mut() {
+					child.drain_into(out);
+				}
+			}
+		}
+	}
+}
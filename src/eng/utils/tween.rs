@@ -0,0 +1,317 @@
+//Keyframed animation of instance properties. A tween is applied on top
+//of whatever instance an object would otherwise render, offsetting its
+//position and rotation and multiplying its scale and tint, so the same
+//tween can be played on anything.
+
+use super::*;
+use crate::window::GLvec4;
+use std::sync::Arc;
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Property {
+	Offset(Vector2<f32>),
+	Scale(Vector2<f32>),
+	//degrees
+	Rotation(f32),
+	Tint(Vector4<f32>),
+}
+
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+	//seconds from the start of the tween
+	pub time: f32,
+	pub value: Property,
+	//easing of the approach to this keyframe from the previous one
+	pub ease: Curve,
+}
+
+type Callback = Arc<dyn Fn() + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Tween {
+	pub start: Instant,
+	pub repeat: f32, //Use f32::INFINITY to repeat forever
+	//keyframes of one property each, in order of time
+	tracks: Vec<Vec<Keyframe>>,
+	//called once each, when the tween first passes the given time
+	callbacks: Vec<(f32, Callback)>,
+	//age at the last poll
+	polled: Option<f32>,
+}
+
+impl Property {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		use Property::*;
+		match (self, to) {
+			(Offset(a), Offset(b)) => Offset(a.lerp(b, t)),
+			(Scale(a), Scale(b)) => Scale(a.lerp(b, t)),
+			(Rotation(a), Rotation(b)) => Rotation(a + (b - a) * t),
+			(Tint(a), Tint(b)) => Tint(a.lerp(b, t)),
+			_ => panic!("Attempted to interpolate between different properties."),
+		}
+	}
+
+	fn same_kind(&self, other: &Self) -> bool {
+		std::mem::discriminant(self) == std::mem::discriminant(other)
+	}
+
+	pub fn apply(self, inst: Instance) -> Instance {
+		use Property::*;
+		match self {
+			Offset(v) => Instance {
+				position: (v.x + inst.position.0, v.y + inst.position.1).into(),
+				..inst
+			},
+			Scale(v) => inst.scale2(v.x, v.y),
+			Rotation(r) => Instance {
+				rotation: (r + inst.rotation.0).into(),
+				..inst
+			},
+			Tint(c) => {
+				let GLvec4(r, g, b, a) = inst.color_tint;
+				Instance {
+					color_tint: (c.x * r, c.y * g, c.z * b, c.w * a).into(),
+					..inst
+				}
+			}
+		}
+	}
+}
+
+impl Tween {
+	//Starts at `now`, which should be `External::now` so that it keeps
+	//time with everything else.
+	pub fn new(now: Instant) -> Self {
+		Self {
+			start: now,
+			repeat: 1.,
+			tracks: vec![],
+			callbacks: vec![],
+			polled: None,
+		}
+	}
+
+	pub fn key(mut self, time: f32, value: Property, ease: Curve) -> Self {
+		let key = Keyframe { time, value, ease };
+		match self
+			.tracks
+			.iter_mut()
+			.find(|t| t[0].value.same_kind(&value))
+		{
+			Some(track) => {
+				let i = track.partition_point(|k| k.time <= time);
+				track.insert(i, key);
+			}
+			None => self.tracks.push(vec![key]),
+		}
+		self
+	}
+
+	pub fn repeat(self, repeat: f32) -> Self {
+		Self { repeat, ..self }
+	}
+
+	//Plays `next` once this tween's keyframes have all been reached.
+	pub fn then(mut self, next: Tween) -> Self {
+		let offset = self.duration();
+		for track in next.tracks {
+			for key in track {
+				self = self.key(key.time + offset, key.value, key.ease);
+			}
+		}
+		self.callbacks.extend(
+			next.callbacks
+				.into_iter()
+				.map(|(time, f)| (time + offset, f)),
+		);
+		self
+	}
+
+	//Called once the keyframes given so far have all been reached.
+	pub fn on_complete<F: Fn() + Send + Sync + 'static>(mut self, f: F) -> Self {
+		self.callbacks.push((self.duration(), Arc::new(f)));
+		self
+	}
+
+	pub fn duration(&self) -> f32 {
+		self.tracks
+			.iter()
+			.filter_map(|t| t.last())
+			.map(|k| k.time)
+			.fold(0., f32::max)
+	}
+
+	pub fn age(&self, now: Instant) -> f32 {
+		now.duration_since(self.start).as_secs_f32()
+	}
+
+	pub fn finished(&self, now: Instant) -> bool {
+		self.age(now) > self.repeat * self.duration()
+	}
+
+	pub fn restart(&mut self, now: Instant) {
+		self.start = now;
+		self.polled = None;
+	}
+
+	//Time into the current repetition.
+	fn local_time(&self, now: Instant) -> f32 {
+		let duration = self.duration();
+		if duration <= 0. {
+			return 0.;
+		}
+
+		let age = self.age(now);
+		if age >= self.repeat * duration {
+			duration
+		} else {
+			age % duration
+		}
+	}
+
+	fn value(track: &[Keyframe], time: f32) -> Property {
+		let next = track.partition_point(|k| k.time <= time);
+		if next == 0 {
+			return track[0].value;
+		}
+		if next == track.len() {
+			return track[next - 1].value;
+		}
+
+		let (from, to) = (track[next - 1], track[next]);
+		let t = (time - from.time) / (to.time - from.time);
+		from.value.lerp(to.value, (to.ease)(t))
+	}
+
+	pub fn apply(&self, now: Instant, inst: Instance) -> Instance {
+		let time = self.local_time(now);
+		self.tracks
+			.iter()
+			.fold(inst, |inst, track| Self::value(track, time).apply(inst))
+	}
+
+	//Runs the callbacks which have come due since the last poll. Should be
+	//called from GameObject::update, since callbacks may have side effects.
+	pub fn poll(&mut self, now: Instant) {
+		let age = self.age(now).min(self.repeat * self.duration());
+		for (time, f) in &self.callbacks {
+			if *time <= age && self.polled.is_none_or(|polled| *time > polled) {
+				f();
+			}
+		}
+		self.polled = Some(age);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::time::Duration;
+
+	fn at(start: Instant, secs: f32) -> Instant {
+		start + Duration::from_secs_f32(secs)
+	}
+
+	fn scale(tween: &Tween, now: Instant) -> f32 {
+		tween.apply(now, Instance::default()).scale.0
+	}
+
+	fn grow(start: Instant) -> Tween {
+		let size = |s| Property::Scale((s, s).into());
+		Tween::new(start)
+			.key(0., size(1.), ease::LINEAR)
+			.key(1., size(3.), ease::LINEAR)
+	}
+
+	fn close(a: f32, b: f32) -> bool {
+		(a - b).abs() < 1e-4
+	}
+
+	#[test]
+	fn easings_run_from_zero_to_one() {
+		use ease::*;
+		let curves = [
+			LINEAR,
+			SIN,
+			CUBIC_IN,
+			CUBIC_OUT,
+			CUBIC_IN_OUT,
+			ELASTIC_OUT,
+			BACK_IN,
+			BACK_OUT,
+		];
+		for curve in curves {
+			assert!(close(curve(0.), 0.) && close(curve(1.), 1.));
+		}
+
+		assert!(close(CUBIC_IN_OUT(0.5), 0.5));
+		//back pulls away before it moves, and elastic overshoots
+		assert!(BACK_IN(0.2) < 0.);
+		assert!((0..100).any(|i| ELASTIC_OUT(i as f32 / 100.) > 1.));
+	}
+
+	#[test]
+	fn interpolates_between_keyframes() {
+		let start = Instant::now();
+		let tween = grow(start);
+
+		assert_eq!(tween.duration(), 1.);
+		assert!(close(scale(&tween, at(start, 0.)), 1.));
+		assert!(close(scale(&tween, at(start, 0.25)), 1.5));
+		assert!(close(scale(&tween, at(start, 2.)), 3.));
+		assert!(!tween.finished(at(start, 0.5)));
+		assert!(tween.finished(at(start, 1.5)));
+
+		let repeated = grow(start).repeat(2.);
+		assert!(close(scale(&repeated, at(start, 1.25)), 1.5));
+		assert!(!repeated.finished(at(start, 1.5)));
+	}
+
+	#[test]
+	fn then_plays_in_sequence() {
+		let start = Instant::now();
+		let shrink = Tween::new(start)
+			.key(0., Property::Scale((3., 3.).into()), ease::LINEAR)
+			.key(1., Property::Scale((1., 1.).into()), ease::LINEAR);
+		let tween = grow(start).then(shrink);
+
+		assert_eq!(tween.duration(), 2.);
+		assert!(close(scale(&tween, at(start, 0.5)), 2.));
+		assert!(close(scale(&tween, at(start, 1.5)), 2.));
+		assert!(close(scale(&tween, at(start, 2.)), 1.));
+	}
+
+	//Callbacks run once each, when a poll first passes their time, from
+	//the tween they were given to or one it was followed by.
+	#[test]
+	fn polls_run_callbacks_once() {
+		let start = Instant::now();
+		let calls = Arc::new(AtomicUsize::new(0));
+		let counter = |by| {
+			let calls = calls.clone();
+			move || {
+				calls.fetch_add(by, Ordering::Relaxed);
+			}
+		};
+
+		let mut tween = grow(start)
+			.on_complete(counter(1))
+			.then(grow(start).on_complete(counter(10)));
+		let mut poll = |secs| {
+			tween.poll(at(start, secs));
+			calls.load(Ordering::Relaxed)
+		};
+
+		assert_eq!(poll(0.5), 0);
+		assert_eq!(poll(1.), 1);
+		assert_eq!(poll(1.5), 1);
+		assert_eq!(poll(3.), 11);
+		assert_eq!(poll(4.), 11);
+
+		tween.restart(at(start, 5.));
+		tween.poll(at(start, 7.));
+		assert_eq!(calls.load(Ordering::Relaxed), 22);
+	}
+}
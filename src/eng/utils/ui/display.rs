@@ -20,6 +20,10 @@ impl<T: TextureType> GameObject for Display<T> {
 	type Scene = ();
 	type Action = UIAction;
 
+	fn layer(&self) -> Layer {
+		Layer::Ui
+	}
+
 	fn instance(&self, external: &External) -> Option<Instance> {
 		Some(Instance {
 			screen_relative: GLbool::True,
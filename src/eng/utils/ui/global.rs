@@ -30,7 +30,7 @@ impl GameObject for Parent {
 		None
 	}
 
-	fn render(&self, external: &External, out: &mut Vec<Instance>) {
+	fn render(&self, external: &External, out: &mut Layers) {
 		for child in &self.children {
 			child.render(external, out);
 		}
@@ -0,0 +1,48 @@
+use super::*;
+
+pub struct Label {
+	pub text: Text,
+	lcl_rect: UIRect,
+	gbl_rect: UIRect,
+}
+
+impl Label {
+	pub fn new(text: Text) -> Self {
+		Self {
+			text: text.screen(),
+			lcl_rect: Default::default(),
+			gbl_rect: Default::default(),
+		}
+	}
+}
+
+impl GameObject for Label {
+	type Scene = ();
+	type Action = UIAction;
+
+	fn layer(&self) -> Layer {
+		Layer::Ui
+	}
+
+	fn render(&self, external: &External, out: &mut Layers) {
+		let text = Text {
+			position: self.gbl_rect.offset,
+			..self.text.clone()
+		};
+		text.render(external, out, self.layer());
+	}
+}
+
+impl UIElement for Label {
+	fn rect(&self) -> &UIRect {
+		&self.lcl_rect
+	}
+
+	fn rect_mut(&mut self) -> &mut UIRect {
+		&mut self.lcl_rect
+	}
+
+	fn propagate_global(&mut self, parent: &UIRect) {
+		self.gbl_rect = self.lcl_rect.globalize(parent);
+	}
+}
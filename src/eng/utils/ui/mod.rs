@@ -1,9 +1,11 @@
 mod display;
 mod global;
+mod label;
 mod rect;
 
 pub use display::*;
 pub use global::*;
+pub use label::*;
 pub use rect::*;
 
 use super::*;
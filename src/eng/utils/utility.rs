@@ -0,0 +1,330 @@
+//Utility-based decisions. Each choice is scored by how well the sensed
+//inputs suit it, so that tuning a behaviour means adjusting curves and
+//weights rather than rewriting branches. Inputs are identified by any
+//copyable key, and read through a closure when choosing.
+
+use super::*;
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResponseCurve {
+	Constant(f32),
+	Linear { slope: f32, offset: f32 },
+	Power { exponent: f32 },
+	//S-shaped, centered on `midpoint`
+	Logistic { steepness: f32, midpoint: f32 },
+	//1 at or above the threshold, 0 below
+	Step { threshold: f32 },
+	Inverse,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Consideration<I> {
+	pub input: I,
+	//range of raw input values, mapped onto [0, 1] before the curve
+	pub range: (f32, f32),
+	pub curve: ResponseCurve,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Choice<T, I> {
+	pub choice: T,
+	pub weight: f32,
+	pub considerations: Vec<Consideration<I>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum Selection {
+	Max,
+	WeightedRandom,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reasoner<T, I> {
+	pub choices: Vec<Choice<T, I>>,
+	pub selection: Selection,
+}
+
+impl ResponseCurve {
+	pub fn eval(&self, x: f32) -> f32 {
+		use ResponseCurve::*;
+		let y = match *self {
+			Constant(c) => c,
+			Linear { slope, offset } => slope * x + offset,
+			Power { exponent } => x.powf(exponent),
+			Logistic {
+				steepness,
+				midpoint,
+			} => 1. / (1. + (-steepness * (x - midpoint)).exp()),
+			Step { threshold } => (x >= threshold) as u32 as f32,
+			Inverse => 1. - x,
+		};
+		y.clamp(0., 1.)
+	}
+}
+
+impl<I: Copy> Consideration<I> {
+	pub fn new(input: I, range: (f32, f32), curve: ResponseCurve) -> Self {
+		Self {
+			input,
+			range,
+			curve,
+		}
+	}
+
+	pub fn score<F: FnMut(I) -> f32>(&self, sense: &mut F) -> f32 {
+		let (lo, hi) = self.range;
+		let value = sense(self.input);
+		//an empty range only tells whether the input has reached it
+		let x = if hi == lo {
+			(value >= lo) as u32 as f32
+		} else {
+			((value - lo) / (hi - lo)).clamp(0., 1.)
+		};
+		self.curve.eval(x)
+	}
+}
+
+impl<T, I: Copy> Choice<T, I> {
+	pub fn new(choice: T, weight: f32) -> Self {
+		Self {
+			choice,
+			weight,
+			considerations: vec![],
+		}
+	}
+
+	pub fn consider(mut self, input: I, range: (f32, f32), curve: ResponseCurve) -> Self {
+		self.considerations
+			.push(Consideration::new(input, range, curve));
+		self
+	}
+
+	pub fn score<F: FnMut(I) -> f32>(&self, sense: &mut F) -> f32 {
+		let count = self.considerations.len();
+		if count == 0 {
+			return self.weight;
+		}
+
+		//Multiplying many scores in [0, 1] penalises choices with more
+		//considerations, so each is compensated by the number of them.
+		let compensation = 1. - 1. / count as f32;
+
+		let product = self
+			.considerations
+			.iter()
+			.map(|c| {
+				let score = c.score(sense);
+				score + (1. - score) * compensation * score
+			})
+			.product::<f32>();
+
+		self.weight * product
+	}
+}
+
+impl<T: Copy, I: Copy> Reasoner<T, I> {
+	pub fn new(selection: Selection) -> Self {
+		Self {
+			choices: vec![],
+			selection,
+		}
+	}
+
+	pub fn with(mut self, choice: Choice<T, I>) -> Self {
+		self.choices.push(choice);
+		self
+	}
+
+	pub fn scores<F: FnMut(I) -> f32>(&self, mut sense: F) -> Vec<(T, f32)> {
+		self.choices
+			.iter()
+			.map(|c| (c.choice, c.score(&mut sense)))
+			.collect()
+	}
+
+	//None if no choice scores above zero.
+	pub fn choose<F: FnMut(I) -> f32>(&self, sense: F) -> Option<T> {
+		let scores = self.scores(sense);
+		let positive = scores.iter().filter(|(_, score)| *score > 0.);
+
+		match self.selection {
+			Selection::Max => positive
+				.max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+				.map(|&(choice, _)| choice),
+
+			Selection::WeightedRandom => {
+				let total = positive.clone().map(|(_, score)| score).sum::<f32>();
+				let mut rng = random() * total;
+				for &(choice, score) in positive.clone() {
+					if rng < score {
+						return Some(choice);
+					}
+					rng -= score;
+				}
+				positive.last().map(|&(choice, _)| choice)
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Clone, Copy, Debug, PartialEq)]
+	enum Input {
+		Hunger,
+		Distance,
+	}
+
+	#[derive(Clone, Copy, Debug, PartialEq)]
+	enum Action {
+		Eat,
+		Walk,
+		Rest,
+	}
+
+	fn close(a: f32, b: f32) -> bool {
+		(a - b).abs() < 1e-5
+	}
+
+	#[test]
+	fn curves_stay_within_unit_range() {
+		use ResponseCurve::*;
+		assert_eq!(
+			Linear {
+				slope: 2.,
+				offset: 0.
+			}
+			.eval(0.75),
+			1.
+		);
+		assert_eq!(
+			Linear {
+				slope: -1.,
+				offset: 0.5
+			}
+			.eval(0.75),
+			0.
+		);
+		assert_eq!(Power { exponent: 2. }.eval(0.5), 0.25);
+		assert_eq!(Step { threshold: 0.5 }.eval(0.49), 0.);
+		assert_eq!(Step { threshold: 0.5 }.eval(0.5), 1.);
+		assert_eq!(Inverse.eval(0.25), 0.75);
+		let logistic = Logistic {
+			steepness: 10.,
+			midpoint: 0.5,
+		};
+		assert!(close(logistic.eval(0.5), 0.5));
+		assert!(logistic.eval(0.) < 0.01 && logistic.eval(1.) > 0.99);
+	}
+
+	#[test]
+	fn considerations_map_range_onto_curve() {
+		let linear = ResponseCurve::Linear {
+			slope: 1.,
+			offset: 0.,
+		};
+		let mut sense = |_| 15.;
+
+		assert_eq!(
+			Consideration::new((), (10., 20.), linear).score(&mut sense),
+			0.5
+		);
+		assert_eq!(
+			Consideration::new((), (0., 10.), linear).score(&mut sense),
+			1.
+		);
+		//a reversed range scores lower inputs higher
+		assert_eq!(
+			Consideration::new((), (20., 10.), linear).score(&mut sense),
+			0.5
+		);
+
+		//an empty range acts as a threshold rather than dividing by zero
+		assert_eq!(
+			Consideration::new((), (15., 15.), linear).score(&mut sense),
+			1.
+		);
+		assert_eq!(
+			Consideration::new((), (16., 16.), linear).score(&mut sense),
+			0.
+		);
+	}
+
+	#[test]
+	fn choices_are_compensated_for_consideration_count() {
+		let half = ResponseCurve::Constant(0.5);
+		let mut sense = |_| 0.;
+
+		assert_eq!(
+			Choice::<_, Input>::new(Action::Rest, 0.3).score(&mut sense),
+			0.3
+		);
+		assert_eq!(
+			Choice::new(Action::Eat, 1.)
+				.consider(Input::Hunger, (0., 1.), half)
+				.score(&mut sense),
+			0.5
+		);
+
+		//two halves would multiply to a quarter, but each is raised by
+		//half of what it falls short of one, times itself
+		let two = Choice::new(Action::Eat, 1.)
+			.consider(Input::Hunger, (0., 1.), half)
+			.consider(Input::Distance, (0., 1.), half);
+		assert!(close(two.score(&mut sense), 0.625 * 0.625));
+	}
+
+	fn reasoner(selection: Selection) -> Reasoner<Action, Input> {
+		use ResponseCurve::*;
+		Reasoner::new(selection)
+			.with(Choice::new(Action::Eat, 1.).consider(
+				Input::Hunger,
+				(0., 1.),
+				Power { exponent: 2. },
+			))
+			.with(Choice::new(Action::Walk, 0.8).consider(Input::Distance, (0., 100.), Inverse))
+			.with(Choice::new(Action::Rest, 0.2))
+	}
+
+	#[test]
+	fn max_picks_highest_score() {
+		let reasoner = reasoner(Selection::Max);
+		let sense = |hunger, distance| {
+			move |input| match input {
+				Input::Hunger => hunger,
+				Input::Distance => distance,
+			}
+		};
+
+		assert_eq!(reasoner.choose(sense(0.9, 50.)), Some(Action::Eat));
+		assert_eq!(reasoner.choose(sense(0.5, 10.)), Some(Action::Walk));
+		assert_eq!(reasoner.choose(sense(0., 200.)), Some(Action::Rest));
+
+		let scores = reasoner.scores(sense(0.5, 50.));
+		assert_eq!(scores[0], (Action::Eat, 0.25));
+		assert_eq!(scores[1], (Action::Walk, 0.4));
+	}
+
+	#[test]
+	fn nothing_chosen_without_positive_score() {
+		let reasoner = Reasoner::new(Selection::WeightedRandom)
+			.with(Choice::new(Action::Eat, 1.).consider(
+				Input::Hunger,
+				(0., 1.),
+				ResponseCurve::Inverse,
+			))
+			.with(Choice::new(Action::Rest, 0.));
+
+		assert_eq!(reasoner.choose(|_| 1.), None);
+		//NaN inputs score nothing rather than poisoning the comparison
+		assert_eq!(reasoner.choose(|_| f32::NAN), None);
+		assert_eq!(reasoner.choose(|_| 0.), Some(Action::Eat));
+	}
+}
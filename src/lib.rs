@@ -0,0 +1,6 @@
+//The engine and the simulation built on it, shared by the game and the
+//tools in src/bin.
+
+pub mod eng;
+pub mod window;
+pub mod world;
@@ -1,8 +1,4 @@
-mod eng;
-mod window;
-mod world;
-
-use world::World;
+use ants::{eng, world::World};
 
 fn main() {
 	eng::play::<World>();
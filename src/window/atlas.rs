@@ -0,0 +1,168 @@
+//Packing of textures into a few large images, grouped as the manifest
+//says. Packing is slow for more than a few images, so atlases can be baked
+//ahead of time with `cargo run --bin bake`, and are only packed at startup
+//if no baked atlas is found, or any asset is newer than it.
+
+use super::reng::utils::create_spritesheet;
+
+pub const BAKED_DIR: &str = "assets/baked";
+
+//Atlas of textures the manifest doesn't place in any other.
+pub const DEFAULT_ATLAS: &str = "main";
+
+//Key of the checkerboard, which isn't read from any file.
+pub const CHECKERBOARD: &str = "<checkerboard>";
+
+pub struct Atlas {
+	pub image: image::RgbaImage,
+	pub meta: AtlasMeta,
+}
+
+#[cfg_attr(feature = "assets", derive(serde::Serialize, serde::Deserialize))]
+pub struct AtlasMeta {
+	pub sprites: Vec<AtlasSprite>,
+}
+
+//Where an image was placed, in pixels, and in texture coordinates as
+//(upper left x, upper left y, lower right x, lower right y).
+#[derive(Clone)]
+#[cfg_attr(feature = "assets", derive(serde::Serialize, serde::Deserialize))]
+pub struct AtlasSprite {
+	//file name relative to the asset directory
+	pub file: String,
+	//whether it was turned a quarter counterclockwise to fit
+	pub rotated: bool,
+	pub ul: (u32, u32),
+	pub lr: (u32, u32),
+	pub uv: (f32, f32, f32, f32),
+}
+
+impl Atlas {
+	pub fn pack(mut images: Vec<(String, image::RgbaImage)>) -> Self {
+		//in the same order as create_spritesheet places them
+		images.sort_by_key(|(_, img)| -((img.height() * img.width()) as i32));
+
+		let (files, images): (Vec<_>, Vec<_>) = images.into_iter().unzip();
+		let (image, coords) = create_spritesheet(images);
+		let (width, height) = image.dimensions();
+
+		let sprites = files
+			.into_iter()
+			.zip(coords)
+			.map(|(file, (rotated, ul, lr))| AtlasSprite {
+				file,
+				rotated,
+				ul,
+				lr,
+				uv: (
+					ul.0 as f32 / width as f32,
+					ul.1 as f32 / height as f32,
+					lr.0 as f32 / width as f32,
+					lr.1 as f32 / height as f32,
+				),
+			})
+			.collect();
+
+		Self {
+			image,
+			meta: AtlasMeta { sprites },
+		}
+	}
+
+	pub fn get(&self, file: &str) -> Option<&AtlasSprite> {
+		self.meta.sprites.iter().find(|sprite| sprite.file == file)
+	}
+
+	//Proportion of the atlas covered by sprites rather than padding.
+	pub fn efficiency(&self) -> f32 {
+		let used = self
+			.meta
+			.sprites
+			.iter()
+			.map(|s| (s.lr.0 - s.ul.0) as u64 * (s.lr.1 - s.ul.1) as u64)
+			.sum::<u64>();
+		let (width, height) = self.image.dimensions();
+		used as f32 / (width as u64 * height as u64) as f32
+	}
+
+	#[cfg(feature = "assets")]
+	pub fn save(
+		&self,
+		image_path: &str,
+		meta_path: &str,
+	) -> Result<(), Box<dyn std::error::Error>> {
+		if let Some(dir) = std::path::Path::new(meta_path).parent() {
+			std::fs::create_dir_all(dir)?;
+		}
+
+		self.image.save(image_path)?;
+
+		let meta = ron::ser::to_string_pretty(&self.meta, Default::default())?;
+		std::fs::write(meta_path, meta)?;
+		Ok(())
+	}
+
+	#[cfg(feature = "assets")]
+	pub fn load(image_path: &str, meta_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+		let meta = ron::from_str(&std::fs::read_to_string(meta_path)?)?;
+		let image = image::open(image_path)?.into_rgba8();
+		Ok(Self { image, meta })
+	}
+
+	//Where the atlas of the given name is baked to, as (image, meta).
+	pub fn baked_paths(name: &str) -> (String, String) {
+		(
+			format!("{}/{}.png", BAKED_DIR, name),
+			format!("{}/{}.ron", BAKED_DIR, name),
+		)
+	}
+
+	//The baked atlas, unless it is missing or older than any of the assets
+	//in `dir`.
+	#[cfg(feature = "assets")]
+	pub fn load_baked(dir: &str, name: &str) -> Option<Self> {
+		let modified =
+			|path: &std::path::Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+		let (image_path, meta_path) = Self::baked_paths(name);
+		let baked = modified(meta_path.as_ref())?;
+		let newest = std::fs::read_dir(dir)
+			.ok()?
+			.filter_map(|entry| entry.ok())
+			.filter(|entry| entry.path().is_file())
+			.filter_map(|entry| modified(&entry.path()))
+			.max()
+			.unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+
+		if newest > baked {
+			println!(
+				"Baked atlas {} is out of date, packing it at startup.",
+				name
+			);
+			return None;
+		}
+
+		Self::load(&image_path, &meta_path)
+			.map_err(|err| log::warn!("Unable to load baked atlas {}: {}", name, err))
+			.ok()
+	}
+
+	//Baked atlases are only read with the `assets` feature, so every atlas
+	//is packed at startup.
+	#[cfg(not(feature = "assets"))]
+	pub fn load_baked(_dir: &str, _name: &str) -> Option<Self> {
+		None
+	}
+}
+
+//Magenta and black, so that it stands out against anything.
+pub fn checkerboard() -> image::RgbaImage {
+	const SIZE: u32 = 8;
+	image::RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+		if (x + y) % 2 == 0 {
+			image::Rgba([255, 0, 255, 255])
+		} else {
+			image::Rgba([0, 0, 0, 255])
+		}
+	})
+}
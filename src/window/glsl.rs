@@ -6,10 +6,38 @@ pub struct Uniform {
     pub ortho: Matrix4<f32>,
 }
 
+//A trail in the buffer faded by trails.comp.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GpuTrail {
+    pub position: GLvec2,
+    //seconds from the same moment as `TrailParams::now`
+    pub born: GLfloat,
+    //0 for to food, 1 for to home
+    pub kind: GLuint,
+}
+
+//What trails.comp needs to fade trails to the current time and draw them.
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TrailParams {
+    pub texture: GLvec4,
+    pub scale: GLvec2,
+    //strength is exp(decay_rate * age)
+    pub decay_rate: GLfloat,
+    pub now: GLfloat,
+    pub threshold: GLfloat,
+    pub atlas: GLuint,
+}
+
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct GLint(pub i32);
 
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GLuint(pub u32);
+
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GLbool {
@@ -17,6 +45,16 @@ pub enum GLbool {
     True,
 }
 
+impl From<bool> for GLbool {
+    fn from(b: bool) -> Self {
+        if b {
+            GLbool::True
+        } else {
+            GLbool::False
+        }
+    }
+}
+
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct GLfloat(pub f32);
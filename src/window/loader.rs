@@ -1,64 +1,119 @@
-use super::{glsl::*, reng::*, types::*};
+use super::{atlas::*, glsl::*, manifest::*, types::*};
 
-pub fn load_textures<T: TextureType>() -> (image::RgbaImage, TextureMap) {
+//Loads and packs every texture in the manifest, returning an image per
+//atlas, in the order of `Instance::atlas`. Problems with individual files
+//are reported, and the checkerboard is used in their place, so that a bad
+//asset doesn't prevent the game from starting.
+pub fn load_textures<T: TextureType>() -> (Vec<image::RgbaImage>, TextureMap, SheetMap) {
 	let mut map = TextureMap::default();
+	let mut sheets = SheetMap::default();
 
-	let list = T::list();
+	let manifest = Manifest::load(MANIFEST).unwrap_or_else(|err| {
+		log::warn!("{}", err);
+		Manifest::fallback::<T>()
+	});
 
-	let mut rgba_images = list
-		.iter()
-		.map(|text| {
-			let file_name = format!("assets/{}.png", text.name());
-			image::open(file_name)
-				.expect("Unable to open image texture asset.")
-				.into_rgba8()
-		})
-		.collect::<Vec<_>>();
+	for text in T::list() {
+		if !manifest
+			.textures
+			.iter()
+			.any(|entry| entry.name == text.name())
+		{
+			log::warn!("Texture {} is not listed in {}.", text.name(), MANIFEST);
+		}
+	}
+
+	let names = manifest.atlases();
+	let mut list = manifest.textures;
+	list.push(TextureEntry {
+		file: Some(CHECKERBOARD.to_owned()),
+		..TextureEntry::new(MISSING_TEXTURE)
+	});
+
+	let mut images = vec![];
 
-	let img_size = |img: &image::RgbaImage| (img.height() * img.width()) as i32;
+	for (index, name) in names.iter().enumerate() {
+		let entries: Vec<_> = list.iter().filter(|entry| entry.atlas == *name).collect();
 
-	let mut sorted_iter = list.iter().enumerate().collect::<Vec<_>>();
-	sorted_iter.sort_by_key(|(index, _text)| -img_size(&rgba_images[*index]));
+		let atlas = Atlas::load_baked(ASSET_DIR, name).unwrap_or_else(|| pack(&entries));
+		let checkerboard = atlas
+			.get(CHECKERBOARD)
+			.expect("Atlas is missing the checkerboard texture.");
 
-	rgba_images.sort_by_key(|e| -img_size(e));
+		for entry in entries {
+			let sprite = atlas.get(&entry.file_name()).unwrap_or_else(|| {
+				log::warn!("{} is missing from atlas {}.", entry.file_name(), name);
+				checkerboard
+			});
 
-	let spritesheet = utils::create_spritesheet(rgba_images);
+			let AtlasSprite {
+				rotated,
+				ul,
+				lr,
+				uv,
+				..
+			} = *sprite;
 
-	let image_dims = spritesheet.0.dimensions();
+			let texture = GLvec4(uv.0, uv.1, uv.2, uv.3);
 
-	let pixel_to_text_coord = |(x, y)| {
-		let norm_x = x as f32 / image_dims.0 as f32;
-		let norm_y = y as f32 / image_dims.1 as f32;
-		(norm_x, norm_y)
-	};
+			let mut width = lr.0 as f32 - ul.0 as f32;
+			let mut height = lr.1 as f32 - ul.1 as f32;
 
-	for (text, &(rotated, ul, lr)) in sorted_iter
-		.iter()
-		.map(|(_index, text)| text)
-		.zip(&spritesheet.1)
-	{
-		let (ulx, uly) = pixel_to_text_coord(ul);
-		let (lrx, lry) = pixel_to_text_coord(lr);
+			if rotated {
+				std::mem::swap(&mut width, &mut height);
+			}
 
-		let texture = GLvec4(ulx, uly, lrx, lry);
+			let mut sheet = SpriteSheet {
+				rotated,
+				..entry.sheet.clone()
+			};
 
-		let mut width = lr.0 as f32 - ul.0 as f32;
-		let mut height = lr.1 as f32 - ul.1 as f32;
+			if sheet.frame_count() == 0 {
+				log::warn!("Texture {} has no frames, using one.", entry.name);
+				sheet = SpriteSheet {
+					rotated,
+					..SpriteSheet::single()
+				};
+			}
 
-		if rotated {
-			std::mem::swap(&mut width, &mut height);
+			map.insert(
+				entry.name.clone(),
+				Instance {
+					texture,
+					color_tint: entry.tint.into(),
+					scale: (width / sheet.columns as f32, height / sheet.rows as f32).into(),
+					rotation: if rotated { 90. } else { 0. }.into(),
+					pivot: entry.pivot_offset().into(),
+					atlas: GLuint(index as u32),
+					..Default::default()
+				},
+			);
+
+			sheets.insert(entry.name.clone(), sheet);
 		}
 
-		map.insert(
-			text.name(),
-			Instance {
-				texture,
-				scale: (width, height / text.frame_count() as f32).into(),
-				rotation: if rotated { 90. } else { 0. }.into(),
-				..Default::default()
-			},
-		);
+		images.push(atlas.image);
+	}
+
+	(images, map, sheets)
+}
+
+//Packs the images the entries refer to, leaving out any which can't be
+//loaded, so that the checkerboard is used in their place.
+fn pack(list: &[&TextureEntry]) -> Atlas {
+	let mut images = vec![(CHECKERBOARD.to_owned(), checkerboard())];
+
+	for entry in list {
+		let file = entry.file_name();
+		if images.iter().any(|(f, _)| *f == file) {
+			continue;
+		}
+
+		match entry.load_image() {
+			Ok(img) => images.push((file, img)),
+			Err(err) => log::warn!("{}", err),
+		}
 	}
 
-	(spritesheet.0, map)
+	Atlas::pack(images)
 }
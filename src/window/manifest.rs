@@ -0,0 +1,151 @@
+//The list of textures to load, read from `assets/manifest.ron` at startup
+//so that sprites can be added or changed without recompiling.
+
+use super::{atlas::DEFAULT_ATLAS, types::*};
+
+pub const ASSET_DIR: &str = "assets";
+pub const MANIFEST: &str = "assets/manifest.ron";
+
+//Name of the checkerboard shown in place of textures which couldn't be
+//loaded, or were never listed.
+pub const MISSING_TEXTURE: &str = "Missing";
+
+#[cfg_attr(feature = "assets", derive(serde::Deserialize))]
+pub struct Manifest {
+	pub textures: Vec<TextureEntry>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "assets", derive(serde::Deserialize))]
+pub struct TextureEntry {
+	pub name: String,
+	//relative to the asset directory, `{name}.png` if not given
+	#[cfg_attr(feature = "assets", serde(default))]
+	pub file: Option<String>,
+	#[cfg_attr(feature = "assets", serde(default = "SpriteSheet::single"))]
+	pub sheet: SpriteSheet,
+	//point about which the sprite is placed and rotated, from its upper
+	//left corner (0, 0) to its lower right corner (1, 1)
+	#[cfg_attr(feature = "assets", serde(default = "TextureEntry::centre"))]
+	pub pivot: (f32, f32),
+	#[cfg_attr(feature = "assets", serde(default = "TextureEntry::white"))]
+	pub tint: (f32, f32, f32, f32),
+	//name of the atlas it is packed into
+	#[cfg_attr(feature = "assets", serde(default = "TextureEntry::default_atlas"))]
+	pub atlas: String,
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+	Read(String, std::io::Error),
+	#[cfg(feature = "assets")]
+	Parse(String, ron::error::SpannedError),
+	//the manifest is only read with the `assets` feature
+	#[cfg(not(feature = "assets"))]
+	Unsupported(String),
+	Image(String, image::ImageError),
+}
+
+impl Manifest {
+	#[cfg(feature = "assets")]
+	pub fn load(path: &str) -> Result<Self, AssetError> {
+		let text =
+			std::fs::read_to_string(path).map_err(|err| AssetError::Read(path.to_owned(), err))?;
+		ron::from_str(&text).map_err(|err| AssetError::Parse(path.to_owned(), err))
+	}
+
+	#[cfg(not(feature = "assets"))]
+	pub fn load(path: &str) -> Result<Self, AssetError> {
+		Err(AssetError::Unsupported(path.to_owned()))
+	}
+
+	//Every texture the game refers to, from `{name}.png`, for when the
+	//manifest itself can't be read.
+	pub fn fallback<T: TextureType>() -> Self {
+		Self {
+			textures: T::list()
+				.into_iter()
+				.map(|text| TextureEntry {
+					sheet: SpriteSheet::strip(text.frame_count()),
+					..TextureEntry::new(text.name())
+				})
+				.collect(),
+		}
+	}
+
+	//Names of the atlases the textures are packed into, the default first,
+	//then in order of appearance.
+	pub fn atlases(&self) -> Vec<String> {
+		let mut names = vec![DEFAULT_ATLAS.to_owned()];
+		for entry in &self.textures {
+			if !names.contains(&entry.atlas) {
+				names.push(entry.atlas.clone());
+			}
+		}
+		names
+	}
+}
+
+impl TextureEntry {
+	pub fn new(name: &str) -> Self {
+		Self {
+			name: name.to_owned(),
+			file: None,
+			sheet: SpriteSheet::single(),
+			pivot: Self::centre(),
+			tint: Self::white(),
+			atlas: Self::default_atlas(),
+		}
+	}
+
+	fn centre() -> (f32, f32) {
+		(0.5, 0.5)
+	}
+
+	fn white() -> (f32, f32, f32, f32) {
+		(1., 1., 1., 1.)
+	}
+
+	fn default_atlas() -> String {
+		DEFAULT_ATLAS.to_owned()
+	}
+
+	pub fn file_name(&self) -> String {
+		match &self.file {
+			Some(file) => file.clone(),
+			None => format!("{}.png", self.name),
+		}
+	}
+
+	pub fn path(&self) -> String {
+		format!("{}/{}", ASSET_DIR, self.file_name())
+	}
+
+	pub fn load_image(&self) -> Result<image::RgbaImage, AssetError> {
+		let path = self.path();
+		image::open(&path)
+			.map(|img| img.into_rgba8())
+			.map_err(|err| AssetError::Image(path, err))
+	}
+
+	//Offset of the pivot from the centre of the sprite, in the units of
+	//`Instance::pivot`.
+	pub fn pivot_offset(&self) -> (f32, f32) {
+		(self.pivot.0 - 0.5, 0.5 - self.pivot.1)
+	}
+}
+
+impl std::fmt::Display for AssetError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Read(path, err) => write!(f, "Unable to read {}: {}", path, err),
+			#[cfg(feature = "assets")]
+			Self::Parse(path, err) => write!(f, "Invalid asset manifest {}: {}", path, err),
+			#[cfg(not(feature = "assets"))]
+			Self::Unsupported(path) => write!(f, "Not reading {} without the assets feature", path),
+			Self::Image(path, err) => write!(f, "Unable to load image {}: {}", path, err),
+		}
+	}
+}
+
+impl std::error::Error for AssetError {}
@@ -1,10 +1,16 @@
+pub mod atlas;
 pub mod glsl;
 mod loader;
+pub mod manifest;
+mod offscreen;
+mod recorder;
 mod reng;
 mod types;
+mod watcher;
 
 pub use glsl::*;
-use std::time::Instant;
+pub use manifest::MISSING_TEXTURE;
+pub use offscreen::*;
 
 pub use types::*;
 
@@ -13,11 +19,27 @@ const START_WIN_SIZE: winit::dpi::PhysicalSize<f32> = winit::dpi::PhysicalSize {
 	height: 400.0,
 };
 
+const SCREENSHOT_KEY: winit::event::VirtualKeyCode = winit::event::VirtualKeyCode::F2;
+const SCREENSHOT_DIR: &str = "screenshots";
+const RECORD_KEY: winit::event::VirtualKeyCode = winit::event::VirtualKeyCode::F3;
+//most trails kept on the GPU at once, beyond which the oldest are replaced
+const GPU_TRAILS: u32 = 1 << 18;
+
 pub struct WinApi {
 	pub window: winit::window::Window,
 	pub external: External,
-	pub output: Vec<Instance>,
+	pub output: Layers,
 	renderer: reng::Renderer<glsl::Uniform, Instance>,
+	watcher: watcher::AssetWatcher,
+	load_textures: fn() -> (Vec<image::RgbaImage>, TextureMap, SheetMap),
+	screenshot: bool,
+	//kept to hand to the recorder, which renders on a device of its own
+	atlases: Vec<image::RgbaImage>,
+	recorder: Option<recorder::Recorder>,
+	//reused for each layer of the output as it's drawn
+	drawn: Vec<Instance>,
+	//versions of the static sets of instances on the GPU
+	uploaded: fnv::FnvHashMap<&'static str, u64>,
 }
 
 impl WinApi {
@@ -33,33 +55,71 @@ impl WinApi {
 
 		let mut renderer = reng::Renderer::new(&window, 4);
 
-		let (image, texture_map) = loader::load_textures::<Texture>();
-		let texture = renderer.create_texture_from_image(&image);
-		renderer.set_texture(&texture);
+		let (atlases, texture_map, sheets) = loader::load_textures::<Texture>();
+		renderer.set_images(&atlases);
 
 		Self {
 			window,
 			renderer,
-			external: External {
-				scroll: 0.,
-				mouse_pos: cgmath::vec2(0.0, 0.0),
-				left_mouse: ButtonState::Up,
-				right_mouse: ButtonState::Up,
-				keymap: fnv::FnvHashMap::default(),
-				texture_map,
-				camera: Camera {
-					pos: cgmath::vec2(0., 0.),
-					scale: 256.,
-				},
-				win_size: (size.width, size.height),
-				now: Instant::now(),
-				delta: 0.,
-			},
-			output: vec![],
+			external: External::new(texture_map, sheets, (size.width, size.height)),
+			output: Default::default(),
+			drawn: vec![],
+			uploaded: Default::default(),
+			watcher: watcher::AssetWatcher::new(),
+			load_textures: loader::load_textures::<Texture>,
+			screenshot: false,
+			atlases,
+			recorder: None,
+		}
+	}
+
+	//Repacks and reuploads every texture if any asset has changed, so that
+	//sprites can be edited while the game runs.
+	pub fn reload_assets(&mut self) {
+		if !self.watcher.poll(self.external.now) {
+			return;
+		}
+
+		let (atlases, texture_map, sheets) = (self.load_textures)();
+		self.renderer.set_images(&atlases);
+
+		self.external.texture_map = texture_map;
+		self.external.sheets = sheets;
+		self.output.invalidate();
+		self.uploaded.clear();
+
+		if let Some(recorder) = &mut self.recorder {
+			recorder.set_atlases(&atlases);
 		}
+		self.atlases = atlases;
 	}
 
 	pub fn clear(&mut self) {
+		//the frame is drawn offscreen so that it can be read back, leaving the
+		//previous one in the window
+		self.screenshot = self.external.key(SCREENSHOT_KEY).pressed();
+		if self.screenshot {
+			self.renderer.redirect();
+		}
+
+		//toggled before any trails are laid this frame, so that those read
+		//back for the recorder aren't given to it twice
+		if self.external.key(RECORD_KEY).pressed() {
+			self.recorder = match self.recorder.take() {
+				Some(recorder) => {
+					recorder.stop();
+					None
+				}
+				None => recorder::Recorder::start(
+					self.external.camera,
+					&self.atlases,
+					&self.renderer.read_trails(),
+				)
+				.map_err(|err| log::error!("Unable to start recording: {}", err))
+				.ok(),
+			};
+		}
+
 		//White for debugging purposes.
 		self.output.clear();
 		self.renderer.clear(wgpu::Color::WHITE);
@@ -69,11 +129,52 @@ impl WinApi {
 		self.renderer.set_uniform(glsl::Uniform {
 			ortho: self.external.camera.proj(self.external.aspect()),
 		});
-		self.renderer.draw(&self.output);
+
+		draw_layers(
+			&mut self.renderer,
+			&self.output,
+			&mut self.uploaded,
+			&mut self.drawn,
+		);
+	}
+
+	//Draws the frame again for the recorder, if recording, with `render`
+	//filling its layers as for the window.
+	pub fn record<R>(&mut self, render: R)
+	where
+		R: FnOnce(&External, &mut Layers),
+	{
+		if let Some(recorder) = &mut self.recorder {
+			recorder.record(&mut self.external, &self.output, render);
+		}
+	}
+
+	//Draws debug shapes straight into this frame, between `clear` and
+	//`draw`. They aren't drawn again for the recorder, so shapes that
+	//should be recorded are drawn with `Shapes::new` from `render` instead.
+	pub fn shapes(&mut self, layer: Layer) -> Shapes<'_> {
+		Shapes::new(&self.external, &mut self.output, layer)
 	}
 
 	pub fn submit(&mut self) {
-		self.renderer.submit();
+		if !self.screenshot {
+			self.renderer.submit();
+			return;
+		}
+
+		if let Some(image) = self.renderer.capture() {
+			let stamp = std::time::SystemTime::now()
+				.duration_since(std::time::UNIX_EPOCH)
+				.unwrap_or_default()
+				.as_millis();
+			let path = format!("{}/{}.png", SCREENSHOT_DIR, stamp);
+
+			match std::fs::create_dir_all(SCREENSHOT_DIR).map(|_| image.save(&path)) {
+				Ok(Ok(())) => println!("Saved screenshot to {}.", path),
+				Ok(Err(err)) => log::error!("Unable to save screenshot {}: {}", path, err),
+				Err(err) => log::error!("Unable to create {}: {}", SCREENSHOT_DIR, err),
+			}
+		}
 	}
 
 	pub fn resize(&mut self, dims: winit::dpi::PhysicalSize<u32>) {
@@ -85,3 +186,68 @@ impl WinApi {
 		self.window.id()
 	}
 }
+
+//Draws each layer in turn, starting with its static sets, which are only
+//uploaded when their version differs from that in `uploaded`, and ending
+//with its heatmaps.
+fn draw_layers(
+	renderer: &mut reng::Renderer<glsl::Uniform, Instance>,
+	layers: &Layers,
+	uploaded: &mut fnv::FnvHashMap<&'static str, u64>,
+	drawn: &mut Vec<Instance>,
+) {
+	for (slot, (_, heatmap, _)) in layers.heatmaps().iter().enumerate() {
+		renderer.set_heatmap(
+			slot,
+			(heatmap.width, heatmap.height),
+			&heatmap.values,
+			&heatmap.ramp.0,
+		);
+	}
+
+	step_trails(renderer, layers);
+
+	for layer in Layer::ALL {
+		for (key, instances) in layers.statics(layer) {
+			if instances.is_empty() {
+				continue;
+			}
+
+			if uploaded.get(key.name) != Some(&key.version) {
+				renderer.cache(key.name, instances);
+				uploaded.insert(key.name, key.version);
+			}
+
+			let mut start = 0;
+			for (atlas, batch) in batches(instances) {
+				let end = start + batch.len() as u32;
+				renderer.draw_cached(key.name, atlas, start..end);
+				start = end;
+			}
+		}
+
+		if let Some(trails) = layers.trails().filter(|t| t.layer == Some(layer)) {
+			renderer.draw_trails(trails.params.atlas.0 as usize);
+		}
+
+		layers.flatten(layer, drawn);
+		for (atlas, batch) in batches(drawn) {
+			renderer.draw(atlas, batch);
+		}
+
+		for (slot, (_, _, inst)) in layers
+			.heatmaps()
+			.iter()
+			.enumerate()
+			.filter(|(_, (l, _, _))| *l == layer)
+		{
+			renderer.draw_heatmap(slot, &[inst.pivoted()]);
+		}
+	}
+}
+
+fn step_trails(renderer: &mut reng::Renderer<glsl::Uniform, Instance>, layers: &Layers) {
+	if let Some(trails) = layers.trails() {
+		renderer.step_trails(GPU_TRAILS, &trails.added, trails.params);
+	}
+}
@@ -0,0 +1,87 @@
+//Renders instances into an image of a fixed size rather than a window, so
+//that the world can be drawn without one, e.g. to compare against reference
+//images. Pass `software` to use a CPU adapter where there is no GPU.
+//Creation fails if there is no adapter at all.
+
+use super::{glsl::*, reng, types::*};
+
+pub struct Offscreen {
+	pub size: (u32, u32),
+	renderer: reng::Renderer<Uniform, Instance>,
+	uploaded: fnv::FnvHashMap<&'static str, u64>,
+	drawn: Vec<Instance>,
+}
+
+impl Offscreen {
+	pub fn new(size: (u32, u32), atlases: &[image::RgbaImage], software: bool) -> Option<Self> {
+		let mut renderer = reng::Renderer::new_offscreen(
+			winit::dpi::PhysicalSize::new(size.0, size.1),
+			1,
+			software,
+		)?;
+
+		renderer.set_images(atlases);
+
+		Some(Self {
+			size,
+			renderer,
+			uploaded: Default::default(),
+			drawn: vec![],
+		})
+	}
+
+	pub fn set_atlases(&mut self, atlases: &[image::RgbaImage]) {
+		self.renderer.set_images(atlases);
+		self.uploaded.clear();
+	}
+
+	//Starts from trails read back from another renderer, e.g. the window's,
+	//as if they had been laid here.
+	pub fn seed_trails(&mut self, trails: &[GpuTrail]) {
+		self.renderer
+			.add_trails::<_, TrailParams>(super::GPU_TRAILS, trails);
+	}
+
+	//Keeps up with the trails on the GPU through frames that aren't drawn.
+	pub fn step(&mut self, layers: &Layers) {
+		super::step_trails(&mut self.renderer, layers);
+	}
+
+	pub fn render(&mut self, camera: &Camera, layers: &Layers) -> image::RgbaImage {
+		self.renderer.clear(wgpu::Color::WHITE);
+		self.renderer.set_uniform(Uniform {
+			ortho: camera.proj(self.size.0 as f32 / self.size.1 as f32),
+		});
+
+		super::draw_layers(
+			&mut self.renderer,
+			layers,
+			&mut self.uploaded,
+			&mut self.drawn,
+		);
+
+		self.renderer
+			.capture()
+			.expect("offscreen renderer always has a target")
+	}
+}
+
+//Proportion of pixels where any channel differs by more than `tolerance`,
+//or None if the images aren't the same size.
+pub fn difference(a: &image::RgbaImage, b: &image::RgbaImage, tolerance: u8) -> Option<f32> {
+	if a.dimensions() != b.dimensions() {
+		return None;
+	}
+
+	let differing = a
+		.pixels()
+		.zip(b.pixels())
+		.filter(|(pa, pb)| {
+			pa.0.iter()
+				.zip(pb.0.iter())
+				.any(|(ca, cb)| ca.abs_diff(*cb) > tolerance)
+		})
+		.count();
+
+	Some(differing as f32 / a.pixels().len() as f32)
+}
@@ -0,0 +1,104 @@
+//Saves every few frames as numbered images, for putting together
+//timelapses. Frames are drawn offscreen at a fixed size, with the camera as
+//it was when recording started, so moving or resizing the window doesn't
+//change the result.
+
+use super::{glsl::GpuTrail, offscreen::Offscreen, types::*};
+
+pub const RECORDING_DIR: &str = "recordings";
+
+pub struct Recorder {
+	offscreen: Offscreen,
+	//built for the recorder's view rather than the window's
+	layers: Layers,
+	camera: Camera,
+	dir: String,
+	//only every `every`th frame is saved
+	every: u32,
+	frame: u32,
+	saved: u32,
+}
+
+impl Recorder {
+	pub const SIZE: (u32, u32) = (1280, 720);
+	pub const EVERY: u32 = 10;
+
+	//Trails already laid are given as read back from the window, since
+	//recording starts with none on the GPU.
+	pub fn start(
+		camera: Camera,
+		atlases: &[image::RgbaImage],
+		trails: &[GpuTrail],
+	) -> std::io::Result<Self> {
+		let stamp = std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_secs();
+		let dir = format!("{}/{}", RECORDING_DIR, stamp);
+		std::fs::create_dir_all(&dir)?;
+
+		println!("Recording every {} frames to {}.", Self::EVERY, dir);
+
+		let mut offscreen = Offscreen::new(Self::SIZE, atlases, false).ok_or_else(|| {
+			std::io::Error::new(
+				std::io::ErrorKind::Unsupported,
+				"no graphics adapter to record with",
+			)
+		})?;
+		offscreen.seed_trails(trails);
+
+		Ok(Self {
+			offscreen,
+			layers: Default::default(),
+			camera,
+			dir,
+			every: Self::EVERY,
+			frame: 0,
+			saved: 0,
+		})
+	}
+
+	pub fn set_atlases(&mut self, atlases: &[image::RgbaImage]) {
+		self.offscreen.set_atlases(atlases);
+		self.layers.invalidate();
+	}
+
+	//Has `render` build the frame as seen from the recorder's camera at its
+	//size, so that what is culled or fitted to the view suits the frame
+	//rather than the window. Frames that aren't saved only keep up with the
+	//trails laid in those drawn to the window, `live`.
+	pub fn record<R>(&mut self, external: &mut External, live: &Layers, render: R)
+	where
+		R: FnOnce(&External, &mut Layers),
+	{
+		let due = self.frame.is_multiple_of(self.every);
+		self.frame += 1;
+		if !due {
+			self.offscreen.step(live);
+			return;
+		}
+
+		let window = (external.camera, external.win_size);
+		(external.camera, external.win_size) = (self.camera, Self::SIZE);
+
+		self.layers.clear();
+		render(external, &mut self.layers);
+
+		(external.camera, external.win_size) = window;
+
+		let image = self.offscreen.render(&self.camera, &self.layers);
+		let path = format!("{}/{:06}.png", self.dir, self.saved);
+		self.saved += 1;
+
+		//encoding takes longer than drawing, so is kept off the main thread
+		rayon::spawn(move || {
+			if let Err(err) = image.save(&path) {
+				log::error!("Unable to save frame {}: {}", path, err);
+			}
+		});
+	}
+
+	pub fn stop(self) {
+		println!("Recorded {} frames to {}.", self.saved, self.dir);
+	}
+}
@@ -7,8 +7,16 @@ pub struct RenderData {
 	pub instance_cap: usize,
 	pub encoder: wgpu::CommandEncoder,
 	pub staging_belt: wgpu::util::StagingBelt,
-	pub texture_bg: wgpu::BindGroup,
+	//one per texture, drawn with by index
+	pub texture_bgs: Vec<wgpu::BindGroup>,
 	pub nearest_sampler: wgpu::Sampler,
 	pub current_frame: Option<wgpu::SurfaceTexture>,
+	//rendered to in place of the surface, for good if offscreen, otherwise
+	//until the next submit
+	pub target: Option<wgpu::Texture>,
+	pub offscreen: bool,
+	//made on first use
+	pub trails: Option<super::trails::TrailBuffer>,
+	pub heatmaps: Option<super::heatmap::Heatmaps>,
 	pub cached_buffers: fnv::FnvHashMap<&'static str, (usize, wgpu::BindGroup, wgpu::Buffer)>,
 }
@@ -0,0 +1,245 @@
+//Heatmaps kept in float textures on the GPU, which heatmap.frag colours by
+//looking their values up in a ramp. Each slot keeps its textures between
+//frames, so only the values are written again unless its size changes.
+
+use super::resources::RenderResources2D;
+
+pub struct Heatmaps {
+	pub pipeline: wgpu::RenderPipeline,
+	values_bgl: wgpu::BindGroupLayout,
+	pub slots: Vec<Slot>,
+}
+
+pub struct Slot {
+	pub size: (u32, u32),
+	values: wgpu::Texture,
+	pub values_bg: wgpu::BindGroup,
+	ramp: wgpu::Texture,
+	pub ramp_bg: wgpu::BindGroup,
+	//as last written, so that it is only written again once changed
+	ramp_image: image::RgbaImage,
+}
+
+impl Heatmaps {
+	pub fn new<U, I>(resources: &RenderResources2D<U, I>) -> Self {
+		let device = &resources.device;
+
+		let values_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("heatmap values"),
+			entries: &[
+				wgpu::BindGroupLayoutEntry {
+					binding: 0,
+					count: None,
+					visibility: wgpu::ShaderStages::FRAGMENT,
+					ty: wgpu::BindingType::Texture {
+						multisampled: false,
+						view_dimension: wgpu::TextureViewDimension::D2,
+						sample_type: wgpu::TextureSampleType::Float { filterable: false },
+					},
+				},
+				wgpu::BindGroupLayoutEntry {
+					binding: 1,
+					count: None,
+					visibility: wgpu::ShaderStages::FRAGMENT,
+					ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+				},
+			],
+		});
+
+		let pipeline = {
+			let vert_shader = unsafe {
+				device.create_shader_module_spirv(&wgpu::include_spirv_raw!(
+					"./shaders/default.vert.spv"
+				))
+			};
+
+			let frag_shader = unsafe {
+				device.create_shader_module_spirv(&wgpu::include_spirv_raw!(
+					"./shaders/heatmap.frag.spv"
+				))
+			};
+
+			let layout = &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+				label: None,
+				bind_group_layouts: &[
+					&resources.uniform_bgl,
+					&resources.instance_bgl,
+					&values_bgl,
+					&resources.texture_bgl,
+				],
+				push_constant_ranges: &[],
+			});
+
+			device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+				label: Some("heatmaps"),
+				layout: Some(layout),
+				vertex: wgpu::VertexState {
+					module: &vert_shader,
+					entry_point: "main",
+					buffers: &[],
+				},
+				fragment: Some(wgpu::FragmentState {
+					module: &frag_shader,
+					entry_point: "main",
+					targets: &[Some(wgpu::ColorTargetState {
+						format: resources.surface_conf.format,
+						blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+						write_mask: wgpu::ColorWrites::ALL,
+					})],
+				}),
+				multisample: wgpu::MultisampleState {
+					count: 1,
+					mask: !0,
+					alpha_to_coverage_enabled: false,
+				},
+				primitive: wgpu::PrimitiveState {
+					topology: wgpu::PrimitiveTopology::TriangleStrip,
+					front_face: wgpu::FrontFace::default(),
+					cull_mode: None,
+					unclipped_depth: false,
+					polygon_mode: wgpu::PolygonMode::Fill,
+					conservative: false,
+					strip_index_format: Some(wgpu::IndexFormat::Uint32),
+				},
+				depth_stencil: None,
+				multiview: None,
+			})
+		};
+
+		Self {
+			pipeline,
+			values_bgl,
+			slots: vec![],
+		}
+	}
+
+	//Writes the values and ramp into `slot`, making its textures first if
+	//it is new or either has changed size. Slots are filled in order.
+	pub fn write<U, I>(
+		&mut self,
+		resources: &RenderResources2D<U, I>,
+		sampler: &wgpu::Sampler,
+		slot: usize,
+		size: (u32, u32),
+		values: &[f32],
+		ramp: &image::RgbaImage,
+	) {
+		let fits =
+			|slot: &Slot| slot.size == size && slot.ramp_image.dimensions() == ramp.dimensions();
+
+		if slot == self.slots.len() {
+			self.slots
+				.push(Slot::new(resources, &self.values_bgl, sampler, size, ramp));
+		} else if !fits(&self.slots[slot]) {
+			self.slots[slot] = Slot::new(resources, &self.values_bgl, sampler, size, ramp);
+		}
+
+		let slot = &mut self.slots[slot];
+		write_texture(
+			resources,
+			&slot.values,
+			super::utils::to_char_slice(values),
+			4,
+			size,
+		);
+
+		if slot.ramp_image != *ramp {
+			write_texture(resources, &slot.ramp, ramp.as_raw(), 4, ramp.dimensions());
+			slot.ramp_image.clone_from(ramp);
+		}
+	}
+}
+
+impl Slot {
+	fn new<U, I>(
+		resources: &RenderResources2D<U, I>,
+		values_bgl: &wgpu::BindGroupLayout,
+		sampler: &wgpu::Sampler,
+		size: (u32, u32),
+		ramp: &image::RgbaImage,
+	) -> Self {
+		let device = &resources.device;
+
+		let texture = |label, (width, height): (u32, u32), format| {
+			device.create_texture(&wgpu::TextureDescriptor {
+				label: Some(label),
+				size: wgpu::Extent3d {
+					width,
+					height,
+					depth_or_array_layers: 1,
+				},
+				usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+				mip_level_count: 1,
+				sample_count: 1,
+				format,
+				view_formats: &[],
+				dimension: wgpu::TextureDimension::D2,
+			})
+		};
+
+		let bind_group = |layout, texture: &wgpu::Texture| {
+			device.create_bind_group(&wgpu::BindGroupDescriptor {
+				label: None,
+				layout,
+				entries: &[
+					wgpu::BindGroupEntry {
+						binding: 0,
+						resource: wgpu::BindingResource::TextureView(
+							&texture.create_view(&wgpu::TextureViewDescriptor::default()),
+						),
+					},
+					wgpu::BindGroupEntry {
+						binding: 1,
+						resource: wgpu::BindingResource::Sampler(sampler),
+					},
+				],
+			})
+		};
+
+		let values = texture("heatmap values", size, wgpu::TextureFormat::R32Float);
+		let ramp_texture = texture(
+			"heatmap ramp",
+			ramp.dimensions(),
+			wgpu::TextureFormat::Rgba8UnormSrgb,
+		);
+		write_texture(
+			resources,
+			&ramp_texture,
+			ramp.as_raw(),
+			4,
+			ramp.dimensions(),
+		);
+
+		Self {
+			size,
+			values_bg: bind_group(values_bgl, &values),
+			values,
+			ramp_bg: bind_group(&resources.texture_bgl, &ramp_texture),
+			ramp: ramp_texture,
+			ramp_image: ramp.clone(),
+		}
+	}
+}
+
+fn write_texture<U, I>(
+	resources: &RenderResources2D<U, I>,
+	texture: &wgpu::Texture,
+	data: &[u8],
+	texel_size: u32,
+	(width, height): (u32, u32),
+) {
+	resources.queue.write_texture(
+		texture.as_image_copy(),
+		data,
+		wgpu::ImageDataLayout {
+			offset: 0,
+			bytes_per_row: std::num::NonZeroU32::new(texel_size * width),
+			rows_per_image: std::num::NonZeroU32::new(height),
+		},
+		wgpu::Extent3d {
+			width,
+			height,
+			depth_or_array_layers: 1,
+		},
+	);
+}
@@ -1,5 +1,7 @@
 pub mod data;
+mod heatmap;
 mod resources;
+mod trails;
 pub mod utils;
 
 pub use data::*;
@@ -17,9 +19,27 @@ impl<UniformType: Copy + PartialEq, InstanceType> Renderer<UniformType, Instance
 		(Self::CHUNK_SIZE * std::mem::size_of::<InstanceType>()) as wgpu::BufferAddress;
 
 	pub fn new(win: &winit::window::Window, sample_count: u32) -> Self {
-		let resources =
-			resources::RenderResources2D::<UniformType, InstanceType>::new(win, sample_count);
+		Self::with_resources(resources::RenderResources2D::new(win, sample_count))
+	}
+
+	//Renders into a texture of the given size instead of a window, which can
+	//be read back with `capture`.
+	pub fn new_offscreen(
+		size: winit::dpi::PhysicalSize<u32>,
+		sample_count: u32,
+		software: bool,
+	) -> Option<Self> {
+		let mut renderer = Self::with_resources(resources::RenderResources2D::new_offscreen(
+			size,
+			sample_count,
+			software,
+		)?);
+		renderer.render_data.offscreen = true;
+		renderer.redirect();
+		Some(renderer)
+	}
 
+	fn with_resources(resources: resources::RenderResources2D<UniformType, InstanceType>) -> Self {
 		let uniform_buffer = resources.device.create_buffer(&wgpu::BufferDescriptor {
 			label: Some("Uniform"),
 			size: std::mem::size_of::<UniformType>() as wgpu::BufferAddress,
@@ -44,8 +64,7 @@ impl<UniformType: Copy + PartialEq, InstanceType> Renderer<UniformType, Instance
 
 		let instance_buffer = resources.device.create_buffer(&wgpu::BufferDescriptor {
 			label: Some("Instance"),
-			size: (Self::CHUNK_SIZE * std::mem::size_of::<InstanceType>())
-				as wgpu::BufferAddress,
+			size: (Self::CHUNK_SIZE * std::mem::size_of::<InstanceType>()) as wgpu::BufferAddress,
 			usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
 			mapped_at_creation: false,
 		});
@@ -108,9 +127,13 @@ impl<UniformType: Copy + PartialEq, InstanceType> Renderer<UniformType, Instance
 			instance_cap: Self::CHUNK_SIZE,
 			encoder: resources.device.create_command_encoder(&Default::default()),
 			staging_belt: wgpu::util::StagingBelt::new(Self::DEFAULT_CHUNK_SIZE),
-			texture_bg,
+			texture_bgs: vec![texture_bg],
 			nearest_sampler: sampler,
 			current_frame: None,
+			target: None,
+			offscreen: false,
+			trails: None,
+			heatmaps: None,
 			cached_buffers: Default::default(),
 		};
 
@@ -188,31 +211,63 @@ impl<UniformType: Copy + PartialEq, InstanceType> Renderer<UniformType, Instance
 			.copy_from_slice(inst_slice);
 	}
 
-	pub fn set_texture(&mut self, texture: &wgpu::Texture) {
-		self.render_data.texture_bg =
-			self.resources
-				.device
-				.create_bind_group(&wgpu::BindGroupDescriptor {
-					label: None,
-					layout: &self.resources.texture_bgl,
-					entries: &[
-						wgpu::BindGroupEntry {
-							binding: 0,
-							resource: wgpu::BindingResource::TextureView(
-								&texture.create_view(&wgpu::TextureViewDescriptor::default()),
-							),
-						},
-						wgpu::BindGroupEntry {
-							binding: 1,
-							resource: wgpu::BindingResource::Sampler(
-								&self.render_data.nearest_sampler,
-							),
-						},
-					],
-				});
+	pub fn set_textures(&mut self, textures: &[wgpu::Texture]) {
+		self.render_data.texture_bgs = textures
+			.iter()
+			.map(|texture| self.texture_bg(texture))
+			.collect();
+	}
+
+	fn texture_bg(&self, texture: &wgpu::Texture) -> wgpu::BindGroup {
+		self.resources
+			.device
+			.create_bind_group(&wgpu::BindGroupDescriptor {
+				label: None,
+				layout: &self.resources.texture_bgl,
+				entries: &[
+					wgpu::BindGroupEntry {
+						binding: 0,
+						resource: wgpu::BindingResource::TextureView(
+							&texture.create_view(&wgpu::TextureViewDescriptor::default()),
+						),
+					},
+					wgpu::BindGroupEntry {
+						binding: 1,
+						resource: wgpu::BindingResource::Sampler(&self.render_data.nearest_sampler),
+					},
+				],
+			})
 	}
 
 	pub fn submit(&mut self) {
+		self.flush();
+		self.finish_frame();
+	}
+
+	//Draws into a texture instead of the window until the next submit, so
+	//that the frame can be captured.
+	pub fn redirect(&mut self) {
+		if self.render_data.target.is_none() {
+			self.render_data.target = Some(self.resources.create_target());
+		}
+	}
+
+	//Submits like `submit`, reading the frame back if it was drawn
+	//offscreen or redirected.
+	pub fn capture(&mut self) -> Option<image::RgbaImage> {
+		self.flush();
+
+		let image = self
+			.render_data
+			.target
+			.as_ref()
+			.map(|target| self.resources.read_texture(target));
+
+		self.finish_frame();
+		image
+	}
+
+	fn flush(&mut self) {
 		let encoder = std::mem::replace(
 			&mut self.render_data.encoder,
 			self.resources.create_encoder(),
@@ -223,18 +278,28 @@ impl<UniformType: Copy + PartialEq, InstanceType> Renderer<UniformType, Instance
 		self.resources.queue.submit(Some(encoder.finish()));
 
 		self.render_data.staging_belt.recall();
+	}
 
+	fn finish_frame(&mut self) {
 		if let Some(frame) = self.render_data.current_frame.take() {
 			frame.present();
 		}
+
+		if !self.render_data.offscreen {
+			self.render_data.target = None;
+		}
 	}
 
 	pub fn resize(&mut self, dims: winit::dpi::PhysicalSize<u32>) {
 		self.resources.resize(dims);
+
+		if self.render_data.target.is_some() {
+			self.render_data.target = Some(self.resources.create_target());
+		}
 	}
 
 	pub fn clear(&mut self, color: wgpu::Color) {
-		let view = &self.get_frame().texture.create_view(&Default::default());
+		let view = &self.frame_view();
 		self.render_data
 			.encoder
 			.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -283,10 +348,12 @@ impl<UniformType: Copy + PartialEq, InstanceType> Renderer<UniformType, Instance
 			.insert(name, (instances.len(), bg, buffer));
 	}
 
-	pub fn draw_cached(&mut self, name: &'static str) {
+	//Draws the instances in `range` of those cached under `name`, with the
+	//texture at `texture`.
+	pub fn draw_cached(&mut self, name: &'static str, texture: usize, range: std::ops::Range<u32>) {
 		self.set_uniform(self.uniform.expect("Uniform not given!"));
 
-		let view = &self.get_frame().texture.create_view(&Default::default());
+		let view = &self.frame_view();
 
 		let cached_buff = self.render_data.cached_buffers.get(&name).unwrap();
 
@@ -309,19 +376,137 @@ impl<UniformType: Copy + PartialEq, InstanceType> Renderer<UniformType, Instance
 		render_pass.set_pipeline(&self.resources.pipeline);
 		render_pass.set_bind_group(0, &self.render_data.uniform_bg, &[]);
 		render_pass.set_bind_group(1, &cached_buff.1, &[]);
-		render_pass.set_bind_group(2, &self.render_data.texture_bg, &[]);
-		render_pass.draw(0..5, 0..cached_buff.0 as u32);
+		render_pass.set_bind_group(2, &self.render_data.texture_bgs[texture], &[]);
+		render_pass.draw(0..4, range);
 
 		drop(render_pass);
 	}
 
-	pub fn draw(&mut self, instances: &[InstanceType]) {
+	//Adds trails to those on the GPU, then fades them all and writes their
+	//instances. The buffer for them is made with room for `capacity` on the
+	//first call.
+	pub fn step_trails<Trail, Params>(&mut self, capacity: u32, added: &[Trail], params: Params) {
+		self.add_trails::<Trail, Params>(capacity, added);
+
+		let trails = self.render_data.trails.as_ref().unwrap();
+		let params = utils::to_char_slice(std::slice::from_ref(&params));
+		if let Some(len) = std::num::NonZeroU64::new(params.len() as u64) {
+			self.render_data
+				.staging_belt
+				.write_buffer(
+					&mut self.render_data.encoder,
+					&trails.params,
+					0,
+					len,
+					&self.resources.device,
+				)
+				.copy_from_slice(params);
+		}
+
+		trails.step(&mut self.render_data.encoder);
+	}
+
+	//Adds trails to those on the GPU as they are, without fading any.
+	pub fn add_trails<Trail, Params>(&mut self, capacity: u32, added: &[Trail]) {
+		let trails = self.render_data.trails.get_or_insert_with(|| {
+			trails::TrailBuffer::new::<_, _, Trail, Params>(&self.resources, capacity)
+		});
+
+		let size = std::mem::size_of::<Trail>() as wgpu::BufferAddress;
+		let belt = &mut self.render_data.staging_belt;
+		let encoder = &mut self.render_data.encoder;
+		let device = &self.resources.device;
+
+		let mut write = |buffer: &wgpu::Buffer, offset: wgpu::BufferAddress, data: &[u8]| {
+			if let Some(len) = std::num::NonZeroU64::new(data.len() as u64) {
+				belt.write_buffer(encoder, buffer, offset, len, device)
+					.copy_from_slice(data);
+			}
+		};
+
+		//only the most recent fit if more are added than there is room for
+		let added = &added[added.len().saturating_sub(trails.capacity as usize)..];
+		let (to_end, wrapped) =
+			added.split_at(added.len().min((trails.capacity - trails.next) as usize));
+
+		write(
+			&trails.trails,
+			trails.next as wgpu::BufferAddress * size,
+			utils::to_char_slice(to_end),
+		);
+		write(&trails.trails, 0, utils::to_char_slice(wrapped));
+
+		trails.next = (trails.next + added.len() as u32) % trails.capacity;
+		trails.len = (trails.len + added.len() as u32).min(trails.capacity);
+	}
+
+	//Reads back the trails on the GPU, oldest first and as faded so far, so
+	//that another renderer can carry on from them with `add_trails`.
+	pub fn read_trails<Trail: Copy + Default>(&mut self) -> Vec<Trail> {
+		if self.render_data.trails.as_ref().map_or(0, |t| t.len) == 0 {
+			return vec![];
+		}
+
+		//so that those added this frame have reached the buffer
+		self.flush();
+
+		let trails = self.render_data.trails.as_ref().unwrap();
+		let mut read = vec![Trail::default(); trails.len as usize];
+		let size = std::mem::size_of_val(read.as_slice());
+		let bytes = self
+			.resources
+			.read_buffer(&trails.trails, size as wgpu::BufferAddress);
+		utils::to_char_slice_mut(&mut read).copy_from_slice(&bytes);
+
+		//once full, the oldest are the next to be overwritten
+		if trails.len == trails.capacity {
+			read.rotate_left(trails.next as usize);
+		}
+		read
+	}
+
+	//Draws the trails last stepped with `step_trails`.
+	pub fn draw_trails(&mut self, texture: usize) {
+		if self.render_data.trails.as_ref().map_or(0, |t| t.len) == 0 {
+			return;
+		}
+
+		self.set_uniform(self.uniform.expect("Uniform not given!"));
+
+		let view = &self.frame_view();
+		let trails = self.render_data.trails.as_ref().unwrap();
+
+		let mut render_pass =
+			self.render_data
+				.encoder
+				.begin_render_pass(&wgpu::RenderPassDescriptor {
+					label: None,
+					color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+						view,
+						resolve_target: None,
+						ops: wgpu::Operations {
+							load: wgpu::LoadOp::Load,
+							store: true,
+						},
+					})],
+					depth_stencil_attachment: None,
+				});
+
+		render_pass.set_pipeline(&self.resources.pipeline);
+		render_pass.set_bind_group(0, &self.render_data.uniform_bg, &[]);
+		render_pass.set_bind_group(1, &trails.instance_bg, &[]);
+		render_pass.set_bind_group(2, &self.render_data.texture_bgs[texture], &[]);
+		render_pass.draw(0..4, 0..trails.len);
+	}
+
+	//Draws with the texture at `texture` in those last given to set_textures.
+	pub fn draw(&mut self, texture: usize, instances: &[InstanceType]) {
 		self.set_uniform(self.uniform.expect("Uniform not given!"));
 
 		for chunk in instances.chunks(Self::CHUNK_SIZE) {
 			self.set_instances(chunk);
 
-			let view = &self.get_frame().texture.create_view(&Default::default());
+			let view = &self.frame_view();
 
 			let mut render_pass =
 				self.render_data
@@ -342,7 +527,7 @@ impl<UniformType: Copy + PartialEq, InstanceType> Renderer<UniformType, Instance
 			render_pass.set_pipeline(&self.resources.pipeline);
 			render_pass.set_bind_group(0, &self.render_data.uniform_bg, &[]);
 			render_pass.set_bind_group(1, &self.render_data.instance_bg, &[]);
-			render_pass.set_bind_group(2, &self.render_data.texture_bg, &[]);
+			render_pass.set_bind_group(2, &self.render_data.texture_bgs[texture], &[]);
 			render_pass.draw(0..4, 0..self.render_data.instance_len as u32);
 		}
 	}
@@ -351,9 +536,85 @@ impl<UniformType: Copy + PartialEq, InstanceType> Renderer<UniformType, Instance
 		self.resources.create_texture_from_image(image)
 	}
 
-	fn get_frame(&mut self) -> &wgpu::SurfaceTexture {
+	pub fn set_images(&mut self, images: &[image::RgbaImage]) {
+		let textures: Vec<_> = images
+			.iter()
+			.map(|image| self.create_texture_from_image(image))
+			.collect();
+		self.set_textures(&textures);
+	}
+
+	//Writes the values, row by row from the top, and colour ramp of the
+	//heatmap drawn from `slot`, reusing its textures from the last frame
+	//while it stays the same size. Slots must be filled in order.
+	pub fn set_heatmap(
+		&mut self,
+		slot: usize,
+		size: (u32, u32),
+		values: &[f32],
+		ramp: &image::RgbaImage,
+	) {
+		let resources = &self.resources;
+		self.render_data
+			.heatmaps
+			.get_or_insert_with(|| heatmap::Heatmaps::new(resources))
+			.write(
+				resources,
+				&self.render_data.nearest_sampler,
+				slot,
+				size,
+				values,
+				ramp,
+			);
+	}
+
+	//Draws the heatmap last written to `slot` onto each instance, in place
+	//of its texture.
+	pub fn draw_heatmap(&mut self, slot: usize, instances: &[InstanceType]) {
+		self.set_uniform(self.uniform.expect("Uniform not given!"));
+		self.set_instances(instances);
+
+		let view = &self.frame_view();
+		let heatmaps = self
+			.render_data
+			.heatmaps
+			.as_ref()
+			.expect("heatmap not given!");
+		let slot = &heatmaps.slots[slot];
+
+		let mut render_pass =
+			self.render_data
+				.encoder
+				.begin_render_pass(&wgpu::RenderPassDescriptor {
+					label: None,
+					color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+						view,
+						resolve_target: None,
+						ops: wgpu::Operations {
+							load: wgpu::LoadOp::Load,
+							store: true,
+						},
+					})],
+					depth_stencil_attachment: None,
+				});
+
+		render_pass.set_pipeline(&heatmaps.pipeline);
+		render_pass.set_bind_group(0, &self.render_data.uniform_bg, &[]);
+		render_pass.set_bind_group(1, &self.render_data.instance_bg, &[]);
+		render_pass.set_bind_group(2, &slot.values_bg, &[]);
+		render_pass.set_bind_group(3, &slot.ramp_bg, &[]);
+		render_pass.draw(0..4, 0..instances.len() as u32);
+	}
+
+	fn frame_view(&mut self) -> wgpu::TextureView {
+		if let Some(target) = &self.render_data.target {
+			return target.create_view(&Default::default());
+		}
+
 		self.render_data
 			.current_frame
 			.get_or_insert_with(|| self.resources.generate_frame())
+			.texture
+			.create_view(&Default::default())
 	}
 }
@@ -2,7 +2,8 @@ pub struct RenderResources2D<UniformType, InstanceType> {
     pub win_size: winit::dpi::PhysicalSize<u32>,
     pub sample_count: u32,
     pub surface_conf: wgpu::SurfaceConfiguration,
-    pub surface: wgpu::Surface,
+    //none when rendering offscreen
+    pub surface: Option<wgpu::Surface>,
     pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
@@ -16,22 +17,54 @@ pub struct RenderResources2D<UniformType, InstanceType> {
 
 impl<UniformType, InstanceType> RenderResources2D<UniformType, InstanceType> {
     pub fn new(win: &winit::window::Window, sample_count: u32) -> Self {
-        let win_size = win.inner_size();
-
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
 
         let surface = unsafe { instance.create_surface(win).unwrap() };
 
+        Self::with_target(
+            instance,
+            Some(surface),
+            win.inner_size(),
+            sample_count,
+            false,
+        )
+        .expect("no suitable graphics adapter")
+    }
+
+    //Resources for rendering into a texture rather than a window, on a
+    //software adapter if `software` is set, so that it works without a GPU.
+    //None if there is no adapter able to render with.
+    pub fn new_offscreen(
+        size: winit::dpi::PhysicalSize<u32>,
+        sample_count: u32,
+        software: bool,
+    ) -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+        Self::with_target(instance, None, size, sample_count, software)
+    }
+
+    fn with_target(
+        instance: wgpu::Instance,
+        surface: Option<wgpu::Surface>,
+        win_size: winit::dpi::PhysicalSize<u32>,
+        sample_count: u32,
+        software: bool,
+    ) -> Option<Self> {
         use futures::executor::block_on;
         let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::LowPower,
-            compatible_surface: Some(&surface),
-            ..Default::default()
-        }))
-        .unwrap();
+            compatible_surface: surface.as_ref(),
+            force_fallback_adapter: software,
+        }))?;
 
         let adapter_features = adapter.features();
 
+        //the shaders are handed over as SPIR-V, untranslated
+        if !adapter_features.contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH) {
+            return None;
+        }
+
         let (device, queue) = futures::executor::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: None,
@@ -40,22 +73,31 @@ impl<UniformType, InstanceType> RenderResources2D<UniformType, InstanceType> {
             },
             None,
         ))
-        .unwrap();
+        .ok()?;
 
-        let surf_caps = surface.get_capabilities(&adapter);
         let surf_fmt = wgpu::TextureFormat::Rgba8UnormSrgb;
 
+        let (present_mode, alpha_mode) = match &surface {
+            Some(surface) => {
+                let surf_caps = surface.get_capabilities(&adapter);
+                (surf_caps.present_modes[0], surf_caps.alpha_modes[0])
+            }
+            None => (wgpu::PresentMode::Fifo, wgpu::CompositeAlphaMode::Auto),
+        };
+
         let surface_conf = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surf_fmt,
             width: win_size.width,
             height: win_size.height,
-            present_mode: surf_caps.present_modes[0],
-            alpha_mode: surf_caps.alpha_modes[0],
+            present_mode,
+            alpha_mode,
             view_formats: vec![],
         };
 
-        surface.configure(&device, &surface_conf);
+        if let Some(surface) = &surface {
+            surface.configure(&device, &surface_conf);
+        }
 
         let uniform_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("uniform_layout"),
@@ -168,7 +210,7 @@ impl<UniformType, InstanceType> RenderResources2D<UniformType, InstanceType> {
         let _unif_marker = std::marker::PhantomData::<UniformType>;
         let _inst_marker = std::marker::PhantomData::<InstanceType>;
 
-        Self {
+        Some(Self {
             win_size,
             sample_count,
             surface_conf,
@@ -182,7 +224,7 @@ impl<UniformType, InstanceType> RenderResources2D<UniformType, InstanceType> {
             texture_bgl,
             _unif_marker,
             _inst_marker,
-        }
+        })
     }
 
     pub fn create_encoder(&mut self) -> wgpu::CommandEncoder {
@@ -190,15 +232,112 @@ impl<UniformType, InstanceType> RenderResources2D<UniformType, InstanceType> {
     }
 
     pub fn generate_frame(&mut self) -> wgpu::SurfaceTexture {
-        match self.surface.get_current_texture() {
+        let surface = self.surface.as_ref().expect("no surface to render to");
+        match surface.get_current_texture() {
             Ok(texture) => texture,
             Err(_) => {
-                self.surface.configure(&self.device, &self.surface_conf);
-                self.surface.get_current_texture().unwrap()
+                surface.configure(&self.device, &self.surface_conf);
+                surface.get_current_texture().unwrap()
             }
         }
     }
 
+    //Texture of the surface's size and format which can be read back.
+    pub fn create_target(&self) -> wgpu::Texture {
+        self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen target"),
+            size: wgpu::Extent3d {
+                width: self.surface_conf.width,
+                height: self.surface_conf.height,
+                depth_or_array_layers: 1,
+            },
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            mip_level_count: 1,
+            sample_count: 1,
+            format: self.surface_conf.format,
+            view_formats: &[],
+            dimension: wgpu::TextureDimension::D2,
+        })
+    }
+
+    //Copies a texture of the surface's size and format back to the CPU.
+    pub fn read_texture(&self, texture: &wgpu::Texture) -> image::RgbaImage {
+        let (width, height) = (self.surface_conf.width, self.surface_conf.height);
+
+        //rows of a copy must be aligned, so are padded and trimmed afterwards
+        let row = 4 * width;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row = row.div_ceil(align) * align;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: (padded_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(padded_row),
+                    rows_per_image: std::num::NonZeroU32::new(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            result.expect("unable to read back frame")
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let data = slice.get_mapped_range();
+        let pixels = data
+            .chunks(padded_row as usize)
+            .flat_map(|line| &line[..row as usize])
+            .copied()
+            .collect();
+        drop(data);
+        buffer.unmap();
+
+        image::RgbaImage::from_raw(width, height, pixels).unwrap()
+    }
+
+    //Copies the first `size` bytes of a buffer made with COPY_SRC back from
+    //the GPU, once the work already submitted is done.
+    pub fn read_buffer(&self, buffer: &wgpu::Buffer, size: wgpu::BufferAddress) -> Vec<u8> {
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(buffer, 0, &readback, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            result.expect("unable to read back buffer")
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let data = slice.get_mapped_range().to_vec();
+        readback.unmap();
+        data
+    }
+
     pub fn create_texture_from_image(&self, image: &image::RgbaImage) -> wgpu::Texture {
         let dimensions = image.dimensions();
 
@@ -242,6 +381,8 @@ impl<UniformType, InstanceType> RenderResources2D<UniformType, InstanceType> {
         self.win_size = size;
         self.surface_conf.width = size.width;
         self.surface_conf.height = size.height;
-        self.surface.configure(&self.device, &self.surface_conf);
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.surface_conf);
+        }
     }
 }
@@ -12,6 +12,10 @@ struct Instance {
 	vec2  translate;
 	float rotation;
 	bool  screen_relative;
+	//applied on the CPU
+	vec2  pivot;
+	//instances are drawn in batches by atlas
+	uint  atlas;
 };
 
 layout(set=1, binding=0, std140)
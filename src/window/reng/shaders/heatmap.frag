@@ -0,0 +1,45 @@
+#version 450
+
+//Colours a grid of values by looking each up in a ramp, so that only the
+//values need uploading each frame.
+
+layout(location=0) in vec2 text_coords;
+layout(location=1) flat in vec4 color_tint;
+
+layout(location=0) out vec4 out_color;
+
+//from 0 to 1 along the ramp, with the first row at the top
+layout(set = 2, binding = 0) uniform texture2D values;
+layout(set = 2, binding = 1) uniform sampler values_samp;
+
+//colours from 0 to 1, in a single row
+layout(set = 3, binding = 0) uniform texture2D ramp;
+layout(set = 3, binding = 1) uniform sampler ramp_samp;
+
+//float textures can't be filtered on every device, so values are blended
+//between the nearest four here
+float value(vec2 coords) {
+	ivec2 size = textureSize(sampler2D(values, values_samp), 0);
+	vec2 p = coords * vec2(size) - 0.5;
+	ivec2 i = ivec2(floor(p));
+	vec2 f = p - vec2(i);
+
+	float v[4];
+	for (int n = 0; n < 4; n++) {
+		ivec2 at = clamp(i + ivec2(n % 2, n / 2), ivec2(0), size - 1);
+		v[n] = texelFetch(sampler2D(values, values_samp), at, 0).r;
+	}
+
+	return mix(mix(v[0], v[1], f.x), mix(v[2], v[3], f.x), f.y);
+}
+
+void main() {
+	//to the centres of the first and last texels of the ramp
+	float n = float(textureSize(sampler2D(ramp, ramp_samp), 0).x);
+	float u = (clamp(value(text_coords), 0.0, 1.0) * (n - 1.0) + 0.5) / n;
+
+	out_color = texture(sampler2D(ramp, ramp_samp), vec2(u, 0.5)) * vec4(pow(color_tint.rgb, vec3(2.2)), color_tint.a);
+	if (out_color.a == 0.0) {
+		discard;
+	}
+}
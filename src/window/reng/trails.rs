@@ -0,0 +1,156 @@
+//Trails kept in a buffer on the GPU, where trails.comp fades them and writes
+//the instances they're drawn with each frame. Once full, new trails
+//overwrite the oldest, which have faded the most.
+
+use super::resources::RenderResources2D;
+
+pub struct TrailBuffer {
+	pipeline: wgpu::ComputePipeline,
+	pub params: wgpu::Buffer,
+	params_bg: wgpu::BindGroup,
+	pub trails: wgpu::Buffer,
+	trails_bg: wgpu::BindGroup,
+	//the instances written by the compute pass, bound for the render pipeline
+	pub instance_bg: wgpu::BindGroup,
+	pub capacity: u32,
+	//slot the next trail is written to
+	pub next: u32,
+	//slots holding trails, which are never emptied once filled
+	pub len: u32,
+}
+
+impl TrailBuffer {
+	//matches local_size_x in trails.comp
+	pub const WORKGROUP: u32 = 64;
+
+	pub fn new<U, I, Trail, Params>(resources: &RenderResources2D<U, I>, capacity: u32) -> Self {
+		let device = &resources.device;
+
+		let storage = |binding| wgpu::BindGroupLayoutEntry {
+			binding,
+			count: None,
+			visibility: wgpu::ShaderStages::COMPUTE,
+			ty: wgpu::BindingType::Buffer {
+				ty: wgpu::BufferBindingType::Storage { read_only: false },
+				has_dynamic_offset: false,
+				min_binding_size: None,
+			},
+		};
+
+		let params_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("trail params"),
+			entries: &[wgpu::BindGroupLayoutEntry {
+				binding: 0,
+				count: None,
+				visibility: wgpu::ShaderStages::COMPUTE,
+				ty: wgpu::BindingType::Buffer {
+					ty: wgpu::BufferBindingType::Uniform,
+					has_dynamic_offset: false,
+					min_binding_size: None,
+				},
+			}],
+		});
+
+		let trails_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("trails"),
+			entries: &[storage(0), storage(1)],
+		});
+
+		let pipeline = {
+			let shader = unsafe {
+				device.create_shader_module_spirv(&wgpu::include_spirv_raw!(
+					"./shaders/trails.comp.spv"
+				))
+			};
+
+			let layout = &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+				label: None,
+				bind_group_layouts: &[&params_bgl, &trails_bgl],
+				push_constant_ranges: &[],
+			});
+
+			device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+				label: Some("trails"),
+				layout: Some(layout),
+				module: &shader,
+				entry_point: "main",
+			})
+		};
+
+		let buffer = |label, size: usize, usage| {
+			device.create_buffer(&wgpu::BufferDescriptor {
+				label: Some(label),
+				size: size as wgpu::BufferAddress,
+				usage,
+				mapped_at_creation: false,
+			})
+		};
+
+		let params = buffer(
+			"trail params",
+			std::mem::size_of::<Params>(),
+			wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+		);
+		//zeroed on creation, so unfilled slots hold trails of no strength
+		let trails = buffer(
+			"trails",
+			capacity as usize * std::mem::size_of::<Trail>(),
+			wgpu::BufferUsages::STORAGE
+				| wgpu::BufferUsages::COPY_DST
+				| wgpu::BufferUsages::COPY_SRC,
+		);
+		let instances = buffer(
+			"trail instances",
+			capacity as usize * std::mem::size_of::<I>(),
+			wgpu::BufferUsages::STORAGE,
+		);
+
+		fn binding(binding: u32, buffer: &wgpu::Buffer) -> wgpu::BindGroupEntry<'_> {
+			wgpu::BindGroupEntry {
+				binding,
+				resource: buffer.as_entire_binding(),
+			}
+		}
+
+		let params_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			label: None,
+			layout: &params_bgl,
+			entries: &[binding(0, &params)],
+		});
+
+		let trails_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			label: None,
+			layout: &trails_bgl,
+			entries: &[binding(0, &trails), binding(1, &instances)],
+		});
+
+		let instance_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			label: None,
+			layout: &resources.instance_bgl,
+			entries: &[binding(0, &instances)],
+		});
+
+		Self {
+			pipeline,
+			params,
+			params_bg,
+			trails,
+			trails_bg,
+			instance_bg,
+			capacity,
+			next: 0,
+			len: 0,
+		}
+	}
+
+	pub fn step(&self, encoder: &mut wgpu::CommandEncoder) {
+		let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+			label: Some("trails"),
+		});
+
+		pass.set_pipeline(&self.pipeline);
+		pass.set_bind_group(0, &self.params_bg, &[]);
+		pass.set_bind_group(1, &self.trails_bg, &[]);
+		pass.dispatch_workgroups(self.capacity.div_ceil(Self::WORKGROUP), 1, 1);
+	}
+}
@@ -0,0 +1,28 @@
+//A grid of values coloured through a ramp on the GPU, such as the density
+//of trails over the view, drawn onto an instance in place of its texture.
+#[derive(Clone, Debug)]
+pub struct Heatmap {
+	pub width: u32,
+	pub height: u32,
+	//row-major from the top row, as in an image, each from 0 to 1 along
+	//the ramp
+	pub values: Vec<f32>,
+	pub ramp: Ramp,
+}
+
+//Colours for values from 0 to 1, as a single row of pixels, so that it can
+//be changed without touching the values.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Ramp(pub image::RgbaImage);
+
+impl Ramp {
+	//enough that steps between colours can't be seen
+	pub const RES: u32 = 256;
+
+	pub fn new(colour: impl Fn(f32) -> image::Rgba<u8>) -> Self {
+		let last = (Self::RES - 1) as f32;
+		Self(image::RgbaImage::from_fn(Self::RES, 1, |x, _| {
+			colour(x as f32 / last)
+		}))
+	}
+}
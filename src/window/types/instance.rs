@@ -9,30 +9,45 @@ pub struct Instance {
 	pub position: GLvec2,
 	pub rotation: GLfloat,
 	pub screen_relative: GLbool,
+	//Offset of the point placed at `position` from the centre, in
+	//proportions of the scale. Applied on the CPU by `pivoted`, so it is
+	//unused by the shader.
+	pub pivot: GLvec2,
+	//Which of the textures packed at startup the texture coordinates refer
+	//to. Instances are drawn in batches by atlas, so it is unused by the
+	//shader.
+	pub atlas: GLuint,
 }
 
 impl Instance {
-	pub fn scale(self, r: f32) -> Self {
-		self.scale2(r, r)
-	}
+	//Moves the instance so that its pivot rather than its centre lies at
+	//its position, turning as the shader does.
+	pub fn pivoted(self) -> Self {
+		let GLvec2(px, py) = self.pivot;
+		if px == 0. && py == 0. {
+			return self;
+		}
+
+		let (x, y) = (-px * self.scale.0, -py * self.scale.1);
+		let (sin, cos) = (-self.rotation.0).to_radians().sin_cos();
 
-	pub fn scale2(self, x: f32, y: f32) -> Self {
 		Self {
-			scale: GLvec2(x * self.scale.0, y * self.scale.1),
+			position: GLvec2(
+				self.position.0 + x * cos - y * sin,
+				self.position.1 + x * sin + y * cos,
+			),
+			pivot: GLvec2(0., 0.),
 			..self
 		}
 	}
 
-	pub fn nth_frame(self, n: u32, out_of: u32) -> Self {
-		let GLvec4(ulx, uly, lrx, lry) = self.texture;
-		let shift = (lry - uly) / out_of as f32;
-		let starty = uly + n as f32 * shift;
-
-		const ANTI_BLEED_MULTIPLIER: f32 = 10. * f32::EPSILON;
-		let anti_bleed = shift * ANTI_BLEED_MULTIPLIER;
+	pub fn scale(self, r: f32) -> Self {
+		self.scale2(r, r)
+	}
 
+	pub fn scale2(self, x: f32, y: f32) -> Self {
 		Self {
-			texture: GLvec4(ulx, starty + anti_bleed, lrx, starty + shift - anti_bleed),
+			scale: GLvec2(x * self.scale.0, y * self.scale.1),
 			..self
 		}
 	}
@@ -47,6 +62,8 @@ impl Default for Instance {
 			position: GLvec2(0.0, 0.0),
 			rotation: GLfloat(0.0),
 			screen_relative: GLbool::False,
+			pivot: GLvec2(0.0, 0.0),
+			atlas: GLuint(0),
 		}
 	}
 }
@@ -0,0 +1,193 @@
+use super::*;
+
+//Instances are drawn a layer at a time, from first to last, and in the
+//order they were pushed within a layer, so that objects can be rendered in
+//any order.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Layer {
+	Terrain,
+	Trails,
+	Items,
+	Entities,
+	Overlay,
+	Ui,
+}
+
+impl Layer {
+	pub const ALL: [Layer; 6] = [
+		Layer::Terrain,
+		Layer::Trails,
+		Layer::Items,
+		Layer::Entities,
+		Layer::Overlay,
+		Layer::Ui,
+	];
+}
+
+//Names a set of instances which rarely change, such as terrain, so that
+//they can be uploaded once and drawn from the copy on the GPU until the
+//version changes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Static {
+	pub name: &'static str,
+	pub layer: Layer,
+	pub version: u64,
+}
+
+struct Cached {
+	key: Static,
+	//pivoted
+	instances: Vec<Instance>,
+	//only sets asked for since the last clear are drawn
+	used: bool,
+}
+
+#[derive(Default)]
+pub struct Layers {
+	layers: [Vec<Instance>; Layer::ALL.len()],
+	//drawn after the other instances of their layer
+	heatmaps: Vec<(Layer, Heatmap, Instance)>,
+	//kept between frames
+	statics: fnv::FnvHashMap<&'static str, Cached>,
+	trails: Option<GpuTrails>,
+}
+
+//Trails faded and drawn on the GPU. They must be given every frame, even
+//when hidden, to keep up with those laid.
+pub struct GpuTrails {
+	//laid since the last frame
+	pub added: Vec<GpuTrail>,
+	pub params: TrailParams,
+	//drawn on this layer, if at all
+	pub layer: Option<Layer>,
+}
+
+impl Layers {
+	pub fn push(&mut self, layer: Layer, instance: Instance) {
+		self.layers[layer as usize].push(instance);
+	}
+
+	pub fn extend(&mut self, layer: Layer, instances: impl IntoIterator<Item = Instance>) {
+		self.layers[layer as usize].extend(instances);
+	}
+
+	//Draws a heatmap generated on the fly onto the instance, in place of
+	//its texture.
+	pub fn heatmap(&mut self, layer: Layer, heatmap: Heatmap, instance: Instance) {
+		self.heatmaps.push((layer, heatmap, instance));
+	}
+
+	//Draws the static set `key` this frame, calling `build` for its
+	//instances only if it hasn't been built at this version before.
+	pub fn cached<B>(&mut self, key: Static, build: B)
+	where
+		B: FnOnce() -> Vec<Instance>,
+	{
+		match self.statics.get_mut(key.name) {
+			Some(cached) if cached.key == key => cached.used = true,
+			_ => {
+				let instances = build().into_iter().map(Instance::pivoted).collect();
+
+				self.statics.insert(
+					key.name,
+					Cached {
+						key,
+						instances,
+						used: true,
+					},
+				);
+			}
+		}
+	}
+
+	pub fn gpu_trails(&mut self, added: &[GpuTrail], params: TrailParams, layer: Option<Layer>) {
+		let trails = self.trails.get_or_insert_with(|| GpuTrails {
+			added: vec![],
+			params,
+			layer,
+		});
+
+		trails.added.extend_from_slice(added);
+		trails.params = params;
+		trails.layer = layer;
+	}
+
+	pub fn trails(&self) -> Option<&GpuTrails> {
+		self.trails.as_ref()
+	}
+
+	//Forgets every static set, e.g. when the textures they refer to move.
+	pub fn invalidate(&mut self) {
+		self.statics.clear();
+	}
+
+	pub fn statics(&self, layer: Layer) -> impl Iterator<Item = (Static, &[Instance])> {
+		self.statics
+			.values()
+			.filter(move |cached| cached.used && cached.key.layer == layer)
+			.map(|cached| (cached.key, cached.instances.as_slice()))
+	}
+
+	pub fn layer(&self, layer: Layer) -> &[Instance] {
+		&self.layers[layer as usize]
+	}
+
+	pub fn heatmaps(&self) -> &[(Layer, Heatmap, Instance)] {
+		&self.heatmaps
+	}
+
+	pub fn clear(&mut self) {
+		for layer in &mut self.layers {
+			layer.clear();
+		}
+		self.heatmaps.clear();
+		self.trails = None;
+		for cached in self.statics.values_mut() {
+			cached.used = false;
+		}
+	}
+
+	//Replaces `out` with the instances of a layer, pivoted.
+	pub fn flatten(&self, layer: Layer, out: &mut Vec<Instance>) {
+		out.clear();
+		out.extend(self.layer(layer).iter().map(|inst| inst.pivoted()));
+	}
+}
+
+//Runs of adjacent instances sharing an atlas, which can each be drawn at
+//once without changing the order they overlap in.
+pub fn batches(instances: &[Instance]) -> impl Iterator<Item = (usize, &[Instance])> {
+	instances
+		.chunk_by(|a, b| a.atlas == b.atlas)
+		.map(|batch| (batch[0].atlas.0 as usize, batch))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn on(atlas: u32) -> Instance {
+		Instance {
+			atlas: GLuint(atlas),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn flatten_keeps_push_order() {
+		let mut layers = Layers::default();
+		layers.extend(Layer::Items, [on(1), on(0), on(0), on(1)]);
+		layers.push(Layer::Entities, on(0));
+
+		let mut out = vec![];
+		layers.flatten(Layer::Items, &mut out);
+
+		let atlases: Vec<_> = out.iter().map(|inst| inst.atlas.0).collect();
+		assert_eq!(atlases, [1, 0, 0, 1]);
+
+		let runs: Vec<_> = batches(&out)
+			.map(|(atlas, run)| (atlas, run.len()))
+			.collect();
+		assert_eq!(runs, [(1, 1), (0, 2), (1, 1)]);
+	}
+}
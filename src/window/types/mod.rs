@@ -1,11 +1,21 @@
 mod camera;
+mod heatmap;
 mod input;
 mod instance;
+mod layer;
+mod shapes;
+mod sheet;
+mod text;
 mod texture;
 
 pub use camera::*;
+pub use heatmap::*;
 pub use input::*;
 pub use instance::*;
+pub use layer::*;
+pub use shapes::*;
+pub use sheet::*;
+pub use text::*;
 pub use texture::*;
 
 use super::glsl::*;
@@ -13,7 +23,8 @@ use cgmath::*;
 use std::time::Instant;
 use winit::event::*;
 
-pub type TextureMap = fnv::FnvHashMap<&'static str, Instance>;
+pub type TextureMap = fnv::FnvHashMap<String, Instance>;
+pub type SheetMap = fnv::FnvHashMap<String, SpriteSheet>;
 
 pub struct External {
 	pub scroll: f32,
@@ -23,13 +34,35 @@ pub struct External {
 	pub keymap: fnv::FnvHashMap<VirtualKeyCode, ButtonState>,
 
 	pub texture_map: TextureMap,
+	pub sheets: SheetMap,
 	pub win_size: (u32, u32),
 	pub camera: Camera,
 	pub now: Instant,
 	pub delta: f32,
+	pub scheduler: crate::eng::Scheduler,
 }
 
 impl External {
+	pub fn new(texture_map: TextureMap, sheets: SheetMap, win_size: (u32, u32)) -> Self {
+		Self {
+			scroll: 0.,
+			mouse_pos: vec2(0.0, 0.0),
+			left_mouse: ButtonState::Up,
+			right_mouse: ButtonState::Up,
+			keymap: fnv::FnvHashMap::default(),
+			texture_map,
+			sheets,
+			camera: Camera {
+				pos: vec2(0., 0.),
+				scale: 256.,
+			},
+			win_size,
+			now: Instant::now(),
+			delta: 0.,
+			scheduler: Default::default(),
+		}
+	}
+
 	pub fn update(&mut self, now: Instant) {
 		self.delta = now.duration_since(self.now).as_secs_f32();
 		self.now = now;
@@ -67,15 +100,33 @@ impl External {
 			|| ((px - cx).abs() < max + dx / 2. && (py - cy).abs() < max + dy / 2.)
 	}
 
-	pub fn clip(&self, out: &mut Vec<Instance>, instance: Instance) {
+	pub fn clip(&self, out: &mut Layers, layer: Layer, instance: Instance) {
 		//clip unseen instances
 		if self.visible(instance) {
-			out.push(instance);
+			out.push(layer, instance);
 		}
 	}
 
 	pub fn instance<T: TextureType>(&self, texture: T) -> Instance {
-		self.texture_map[&texture.name()]
+		match self.texture_map.get(texture.name()) {
+			Some(&inst) => inst,
+			None => self.texture_map[super::MISSING_TEXTURE],
+		}
+	}
+
+	pub fn sheet<T: TextureType>(&self, texture: T) -> &SpriteSheet {
+		match self.sheets.get(texture.name()) {
+			Some(sheet) => sheet,
+			None => &self.sheets[super::MISSING_TEXTURE],
+		}
+	}
+
+	//Frame of the named clip shown `time` seconds in, or the first frame
+	//of the sheet if it has no such clip.
+	pub fn clip_frame<T: TextureType>(&self, texture: T, clip: &str, time: f32) -> Instance {
+		let sheet = self.sheet(texture);
+		let frame = sheet.clip_frame(clip, time).unwrap_or(0);
+		sheet.frame(self.instance(texture), frame)
 	}
 
 	pub fn aspect(&self) -> f32 {
@@ -0,0 +1,143 @@
+use super::*;
+
+//Textures shapes are drawn with: a single white pixel, and a white disc.
+pub const FLAT: &str = "Flat";
+pub const CIRCLE: &str = "Circle";
+
+//Draws lines, rectangles, circles and arrows as instances of plain
+//textures, mostly for debugging. Settings apply to every shape drawn after
+//them.
+pub struct Shapes<'a> {
+	external: &'a External,
+	out: &'a mut Layers,
+	layer: Layer,
+	color: GLvec4,
+	thickness: f32,
+	screen_relative: bool,
+}
+
+impl<'a> Shapes<'a> {
+	//segments in outlines of circles
+	const SEGMENTS: usize = 32;
+
+	pub fn new(external: &'a External, out: &'a mut Layers, layer: Layer) -> Self {
+		Self {
+			external,
+			out,
+			layer,
+			color: GLvec4(0., 0., 0., 1.),
+			thickness: 0.5,
+			screen_relative: false,
+		}
+	}
+
+	pub fn color(&mut self, color: impl Into<GLvec4>) -> &mut Self {
+		self.color = color.into();
+		self
+	}
+
+	pub fn thickness(&mut self, thickness: f32) -> &mut Self {
+		self.thickness = thickness;
+		self
+	}
+
+	//Positioned and sized on the screen, from (-1, -1) to (1, 1), rather
+	//than in the world.
+	pub fn screen(&mut self, screen_relative: bool) -> &mut Self {
+		self.screen_relative = screen_relative;
+		self
+	}
+
+	pub fn line(&mut self, from: Vector2<f32>, to: Vector2<f32>) -> &mut Self {
+		let diff = to - from;
+		self.push(
+			FLAT,
+			Instance {
+				position: ((from + to) / 2.).into(),
+				scale: GLvec2(diff.magnitude(), self.thickness),
+				rotation: (-diff.y.atan2(diff.x).to_degrees()).into(),
+				..Default::default()
+			},
+		)
+	}
+
+	pub fn rect(&mut self, centre: Vector2<f32>, size: Vector2<f32>) -> &mut Self {
+		self.push(
+			FLAT,
+			Instance {
+				position: centre.into(),
+				scale: size.into(),
+				..Default::default()
+			},
+		)
+	}
+
+	pub fn rect_outline(&mut self, centre: Vector2<f32>, size: Vector2<f32>) -> &mut Self {
+		let (hx, hy) = (size.x / 2., size.y / 2.);
+		let corners = [
+			centre + vec2(-hx, -hy),
+			centre + vec2(hx, -hy),
+			centre + vec2(hx, hy),
+			centre + vec2(-hx, hy),
+		];
+
+		for i in 0..corners.len() {
+			self.line(corners[i], corners[(i + 1) % corners.len()]);
+		}
+		self
+	}
+
+	pub fn circle(&mut self, centre: Vector2<f32>, radius: f32) -> &mut Self {
+		self.push(
+			CIRCLE,
+			Instance {
+				position: centre.into(),
+				scale: GLvec2(2. * radius, 2. * radius),
+				..Default::default()
+			},
+		)
+	}
+
+	pub fn circle_outline(&mut self, centre: Vector2<f32>, radius: f32) -> &mut Self {
+		let point = |i: usize| {
+			let theta = std::f32::consts::TAU * i as f32 / Self::SEGMENTS as f32;
+			centre + radius * vec2(theta.cos(), theta.sin())
+		};
+
+		for i in 0..Self::SEGMENTS {
+			self.line(point(i), point(i + 1));
+		}
+		self
+	}
+
+	//A line with a head at `to`, sized by the thickness.
+	pub fn arrow(&mut self, from: Vector2<f32>, to: Vector2<f32>) -> &mut Self {
+		let dir = to - from;
+		if dir.magnitude2() == 0. {
+			return self;
+		}
+
+		let back = -dir.normalize() * 3. * self.thickness;
+		let side = vec2(-back.y, back.x) / 2.;
+
+		self.line(from, to)
+			.line(to, to + back + side)
+			.line(to, to + back - side)
+	}
+
+	//Fills in what is shared by every shape, then clips it like any other
+	//instance.
+	fn push(&mut self, texture: &'static str, inst: Instance) -> &mut Self {
+		let base = self.external.instance(texture);
+		let inst = Instance {
+			texture: base.texture,
+			atlas: base.atlas,
+			color_tint: self.color,
+			screen_relative: self.screen_relative.into(),
+			..inst
+		};
+
+		self.external.clip(self.out, self.layer, inst);
+		self
+	}
+}
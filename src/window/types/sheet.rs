@@ -0,0 +1,127 @@
+use super::*;
+
+//Layout of the frames within a sprite, as given in the asset manifest.
+//Frames are numbered left to right, then top to bottom.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "assets", derive(serde::Deserialize))]
+pub struct SpriteSheet {
+	#[cfg_attr(feature = "assets", serde(default = "SpriteSheet::one"))]
+	pub columns: u32,
+	#[cfg_attr(feature = "assets", serde(default = "SpriteSheet::one"))]
+	pub rows: u32,
+	//seconds each frame is shown for, unless given in `durations`
+	#[cfg_attr(feature = "assets", serde(default = "SpriteSheet::default_frame_time"))]
+	pub frame_time: f32,
+	#[cfg_attr(feature = "assets", serde(default))]
+	pub durations: Vec<f32>,
+	#[cfg_attr(feature = "assets", serde(default))]
+	pub clips: fnv::FnvHashMap<String, Vec<u32>>,
+
+	//whether the sprite was packed on its side
+	#[cfg_attr(feature = "assets", serde(skip))]
+	pub rotated: bool,
+}
+
+impl SpriteSheet {
+	fn default_frame_time() -> f32 {
+		0.1
+	}
+
+	#[cfg(feature = "assets")]
+	fn one() -> u32 {
+		1
+	}
+
+	pub fn single() -> Self {
+		Self::strip(1)
+	}
+
+	//A single column of frames, as sprites were laid out before sheets.
+	pub fn strip(frames: u32) -> Self {
+		Self {
+			columns: 1,
+			rows: frames,
+			frame_time: Self::default_frame_time(),
+			durations: vec![],
+			clips: Default::default(),
+			rotated: false,
+		}
+	}
+
+	pub fn frame_count(&self) -> u32 {
+		self.columns * self.rows
+	}
+
+	pub fn duration(&self, frame: u32) -> f32 {
+		self.durations
+			.get(frame as usize)
+			.copied()
+			.unwrap_or(self.frame_time)
+	}
+
+	pub fn clip(&self, name: &str) -> Option<&[u32]> {
+		self.clips.get(name).map(|frames| frames.as_slice())
+	}
+
+	pub fn clip_duration(&self, name: &str) -> Option<f32> {
+		self.clip(name)
+			.map(|frames| frames.iter().map(|&f| self.duration(f)).sum())
+	}
+
+	//Frame shown `time` seconds into a clip, looping.
+	pub fn clip_frame(&self, name: &str, time: f32) -> Option<u32> {
+		let frames = self.clip(name)?;
+		let total = self.clip_duration(name)?;
+		if total <= 0. {
+			return frames.first().copied();
+		}
+
+		let mut time = time.rem_euclid(total);
+		for &frame in frames {
+			if time < self.duration(frame) {
+				return Some(frame);
+			}
+			time -= self.duration(frame);
+		}
+		frames.last().copied()
+	}
+
+	//Restricts an instance of the whole sheet to one of its frames.
+	pub fn frame(&self, inst: Instance, n: u32) -> Instance {
+		let n = n.min(self.frame_count() - 1);
+		let (col, row) = (n % self.columns, n / self.columns);
+
+		let (u0, u1) = (
+			col as f32 / self.columns as f32,
+			(col + 1) as f32 / self.columns as f32,
+		);
+		let (v0, v1) = (
+			row as f32 / self.rows as f32,
+			(row + 1) as f32 / self.rows as f32,
+		);
+
+		//sprites packed on their side were turned a quarter counterclockwise,
+		//so their columns run from the bottom of the atlas upward
+		let (x0, x1, y0, y1) = if self.rotated {
+			(v0, v1, 1. - u1, 1. - u0)
+		} else {
+			(u0, u1, v0, v1)
+		};
+
+		let GLvec4(ulx, uly, lrx, lry) = inst.texture;
+		let (w, h) = (lrx - ulx, lry - uly);
+
+		const ANTI_BLEED_MULTIPLIER: f32 = 10. * f32::EPSILON;
+		let (bx, by) = (w * ANTI_BLEED_MULTIPLIER, h * ANTI_BLEED_MULTIPLIER);
+
+		Instance {
+			texture: GLvec4(
+				ulx + x0 * w + bx,
+				uly + y0 * h + by,
+				ulx + x1 * w - bx,
+				uly + y1 * h - by,
+			),
+			..inst
+		}
+	}
+}
@@ -0,0 +1,113 @@
+use super::*;
+
+//Texture of the glyphs for the printable ASCII characters, from space
+//onwards, in a grid of equally sized cells.
+pub const FONT: &str = "Font";
+const FIRST_GLYPH: char = ' ';
+const UNKNOWN_GLYPH: char = '?';
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Align {
+	Left,
+	Centre,
+	Right,
+}
+
+//A string laid out in glyphs from the font. The position is of the middle
+//of the first line, at its left edge, centre or right edge depending on the
+//alignment, and lines run downward.
+#[derive(Clone, Debug)]
+pub struct Text {
+	pub string: String,
+	pub position: Vector2<f32>,
+	//height of a line
+	pub size: f32,
+	pub color: GLvec4,
+	pub align: Align,
+	pub screen_relative: bool,
+}
+
+impl Text {
+	pub fn new(string: impl Into<String>, position: Vector2<f32>) -> Self {
+		Self {
+			string: string.into(),
+			position,
+			size: 1.,
+			color: GLvec4(1., 1., 1., 1.),
+			align: Align::Left,
+			screen_relative: false,
+		}
+	}
+
+	pub fn size(self, size: f32) -> Self {
+		Self { size, ..self }
+	}
+
+	pub fn color(self, color: impl Into<GLvec4>) -> Self {
+		Self {
+			color: color.into(),
+			..self
+		}
+	}
+
+	pub fn align(self, align: Align) -> Self {
+		Self { align, ..self }
+	}
+
+	//Positioned and sized on the screen, from (-1, -1) to (1, 1), rather
+	//than in the world.
+	pub fn screen(self) -> Self {
+		Self {
+			screen_relative: true,
+			..self
+		}
+	}
+
+	pub fn instances<'a>(&'a self, external: &'a External) -> impl Iterator<Item = Instance> + 'a {
+		let sheet = external.sheet(FONT);
+		let glyph = external.instance(FONT);
+
+		let width = self.size * glyph.scale.0 / glyph.scale.1;
+		let x_offset = match self.align {
+			Align::Left => 0.,
+			Align::Centre => 0.5,
+			Align::Right => 1.,
+		};
+
+		self.string
+			.lines()
+			.enumerate()
+			.flat_map(move |(row, line)| {
+				let length = line.chars().count() as f32;
+				let start = self.position.x - x_offset * length * width + width / 2.;
+				let y = self.position.y - row as f32 * self.size;
+
+				line.chars().enumerate().filter_map(move |(col, c)| {
+					if c == ' ' {
+						return None;
+					}
+
+					let c = if c.is_ascii_graphic() {
+						c
+					} else {
+						UNKNOWN_GLYPH
+					};
+					let frame = c as u32 - FIRST_GLYPH as u32;
+
+					Some(Instance {
+						position: GLvec2(start + col as f32 * width, y),
+						scale: GLvec2(width, self.size),
+						color_tint: self.color,
+						screen_relative: self.screen_relative.into(),
+						..sheet.frame(glyph, frame)
+					})
+				})
+			})
+	}
+
+	pub fn render(&self, external: &External, out: &mut Layers, layer: Layer) {
+		for inst in self.instances(external) {
+			external.clip(out, layer, inst);
+		}
+	}
+}
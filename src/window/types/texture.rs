@@ -7,3 +7,15 @@ pub trait TextureType: Sized + Clone + Copy {
 		1
 	}
 }
+
+//Any texture in the asset manifest can be referred to by name, including
+//those added without a matching variant in the game's texture type.
+impl TextureType for &'static str {
+	fn list() -> Vec<Self> {
+		vec![]
+	}
+
+	fn name(&self) -> &'static str {
+		self
+	}
+}
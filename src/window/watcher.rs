@@ -0,0 +1,49 @@
+//Polls the asset directory for changes, since the files are few enough
+//that comparing modification times every so often costs next to nothing.
+
+use super::manifest::ASSET_DIR;
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime};
+
+pub struct AssetWatcher {
+	last_poll: Instant,
+	stamps: fnv::FnvHashMap<PathBuf, SystemTime>,
+}
+
+impl AssetWatcher {
+	//seconds between polls
+	const PERIOD: f32 = 0.5;
+
+	pub fn new() -> Self {
+		Self {
+			last_poll: Instant::now(),
+			stamps: Self::scan(),
+		}
+	}
+
+	//Whether any file was added, removed or modified since the last poll.
+	pub fn poll(&mut self, now: Instant) -> bool {
+		if now.duration_since(self.last_poll).as_secs_f32() < Self::PERIOD {
+			return false;
+		}
+		self.last_poll = now;
+
+		let stamps = Self::scan();
+		let changed = stamps != self.stamps;
+		self.stamps = stamps;
+		changed
+	}
+
+	fn scan() -> fnv::FnvHashMap<PathBuf, SystemTime> {
+		let Ok(dir) = std::fs::read_dir(ASSET_DIR) else {
+			return Default::default();
+		};
+
+		dir.filter_map(|entry| {
+			let entry = entry.ok()?;
+			let modified = entry.metadata().ok()?.modified().ok()?;
+			Some((entry.path(), modified))
+		})
+		.collect()
+	}
+}
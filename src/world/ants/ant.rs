@@ -10,6 +10,8 @@ pub struct Ant<Plan: AntPlan> {
 	pub pos: Vector2<f32>,
 	pub dir: Vector2<f32>,
 	pub behavior: Cell<Plan>,
+	//seconds of walking at full speed, which drives the walk cycle
+	pub gait: f32,
 }
 
 impl<Plan: AntPlan> Ant<Plan> {
@@ -19,6 +21,7 @@ impl<Plan: AntPlan> Ant<Plan> {
 			pos,
 			dir,
 			behavior: Cell::new(Plan::spawn(pos, dir)),
+			gait: 0.,
 		}
 	}
 
@@ -27,6 +30,7 @@ impl<Plan: AntPlan> Ant<Plan> {
 			pos,
 			dir: unit_in_dir(dir),
 			behavior: Cell::new(plan),
+			gait: 0.,
 		}
 	}
 }
@@ -60,6 +64,7 @@ impl<Plan: AntPlan> GameObject for Ant<Plan> {
 		//slow down on wide turns
 		let slow_down = self.dir.dot(next_dir).abs();
 
+		self.gait += external.delta * slow_down;
 		self.dir = unit_in_dir(new_ang);
 		self.pos += self
 			.dir
@@ -69,10 +74,13 @@ impl<Plan: AntPlan> GameObject for Ant<Plan> {
 	}
 
 	fn instance(&self, external: &External) -> Option<Instance> {
+		let plan = self.behavior.get();
+		let frame = external.clip_frame(plan.texture(), plan.clip(), self.gait);
 		Some(Instance {
 			position: self.pos.into(),
-			rotation: angle(self.dir).to_degrees().into(),
-			..external.instance(self.behavior.get().texture())
+			//on top of any turn from the sprite being packed on its side
+			rotation: (angle(self.dir).to_degrees() + frame.rotation.0).into(),
+			..frame
 		})
 	}
 }
@@ -87,6 +95,10 @@ impl<Plan: AntPlan> utils::Relax for Ant<Plan> {
 	fn plan_frequency(&self) -> f32 {
 		60.
 	}
+
+	fn focus(&self) -> Option<Vector2<f32>> {
+		Some(self.pos)
+	}
 }
 
 unsafe impl<Plan: AntPlan + Send> Send for Ant<Plan> {}
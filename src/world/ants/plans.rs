@@ -13,6 +13,15 @@ pub trait AntPlan: Sized + Copy + PartialEq {
 
 	fn spawn(pos: Vector2<f32>, dir: Vector2<f32>) -> Self;
 	fn next_plan(&self, ant: &Ant<Self>, world: &World, messenger: &Sender<Dispatch>) -> Self;
-	fn action(&mut self, ant: &Ant<Self>, external: &External) -> (Vector2<f32>, Option<Self::Action>);
+	fn action(
+		&mut self,
+		ant: &Ant<Self>,
+		external: &External,
+	) -> (Vector2<f32>, Option<Self::Action>);
 	fn texture(&self) -> Texture;
+
+	//clip of the texture's sprite sheet to play while moving
+	fn clip(&self) -> &'static str {
+		"walk"
+	}
 }
@@ -50,4 +50,8 @@ impl AntPlan for QueenPlan {
 	fn texture(&self) -> Texture {
 		Texture::Queen
 	}
+
+	fn clip(&self) -> &'static str {
+		"idle"
+	}
 }
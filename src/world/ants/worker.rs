@@ -1,20 +1,41 @@
 use super::*;
 
 use cgmath::*;
+use std::sync::OnceLock;
+use strum_macros::{EnumDiscriminants, EnumIter, IntoStaticStr};
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct WorkerPlan {
 	state: WorkerState,
 	last_trail: Vector2<f32>,
+	//as of the last update, to record changes made while planning
+	logged: WorkerStateKind,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, EnumDiscriminants)]
+#[strum_discriminants(name(WorkerStateKind), derive(EnumIter, IntoStaticStr))]
 enum WorkerState {
 	Wander(Vector2<f32>),
 	GoToFood(Vector2<f32>),
 	GoToHome(Vector2<f32>),
 }
 
+//What a wandering worker weighs up when deciding where to head next.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum WanderChoice {
+	SeekFood,
+	FollowTrail,
+	Explore,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum WorkerInput {
+	//to the nearest food, or infinite if none can be smelled
+	FoodDistance,
+	//total strength of the trails toward food that can be smelled
+	FoodTrail,
+}
+
 impl AntPlan for WorkerPlan {
 	type Action = Trail;
 
@@ -22,71 +43,33 @@ impl AntPlan for WorkerPlan {
 		Self {
 			state: WorkerState::Wander(pos + dir),
 			last_trail: pos,
+			logged: WorkerStateKind::Wander,
 		}
 	}
 
 	fn next_plan(&self, ant: &Ant<Self>, world: &World, messenger: &Sender<Dispatch>) -> Self {
-		use WorkerState::*;
-
-		let state = match self.state {
-			Wander(toward) => {
-				if let Some(food) = world.food.nearest(ant.pos(), Self::SMELL_RAD) {
-					GoToFood(food.pos)
-				} else if ant.pos.distance(toward) < Self::EXPLORATION {
-					let offset = rand_in2d(-0.75, 0.75);
-					Wander(ant.pos + 2. * Self::EXPLORATION * (ant.dir + offset))
-				} else {
-					Wander(toward)
-				}
-			}
-
-			GoToFood(food) => {
-				if world.food.get(food.into()).is_none() {
-					Wander(food)
-				} else if food.distance(ant.pos) < Self::TRAIL_SEP {
-					let message = Dispatch::new(Some(food.into()), Signal::ConsumeFood, 0.);
-					messenger.send(message).expect("Unable to send message.");
-					GoToHome(self.last_trail - ant.pos)
-				} else {
-					self.state
-				}
-			}
-
-			GoToHome(curr) => {
-				let mut sum_dir = curr * Self::PREFER_STRAIGHT;
-
-				for (d, trail) in world
-					.trails
-					.query_with_dist(ant.pos.into(), Self::TRAIL_SMELL_RAD)
-					.filter(|(_, t)| ant.dir.dot(t.dir) < 0.5 && t.ty == Pheromone::ToHome)
-				{
-					let toward_trail = unit_toward(trail.pos, ant.pos);
-					let direction = 0.5 * toward_trail + trail.dir;
-					sum_dir -= direction.normalize_to(trail.strength / (0.1 + d));
-				}
-
-				let normal = sum_dir.normalize();
-
-				if curr.magnitude() == 0. {
-					dbg!("!");
-				}
-
-				if normal != curr {
-					GoToHome(normal)
-				} else {
-					Wander(ant.pos + curr)
-				}
-			}
+		let senses = Senses {
+			agent: ant,
+			scene: world,
+			messenger,
 		};
 
-		Self { state, ..*self }
+		let mut plan = *self;
+		Self::behavior().tick(&senses, &mut plan);
+		plan
 	}
 
 	fn action(
 		&mut self,
 		ant: &Ant<Self>,
-		_external: &External,
+		external: &External,
 	) -> (Vector2<f32>, Option<Self::Action>) {
+		let kind = self.state.into();
+		if kind != self.logged {
+			TRANSITIONS.record(self.logged, kind);
+			self.logged = kind;
+		}
+
 		use WorkerState::*;
 		let (dir, pheromone) = match self.state {
 			Wander(toward) => (toward - ant.pos, Pheromone::ToHome),
@@ -98,7 +81,7 @@ impl AntPlan for WorkerPlan {
 
 		let trail = if ant.pos.distance2(self.last_trail) > Self::TRAIL_SEP.powf(2.) {
 			self.last_trail = ant.pos;
-			Some(Trail::new(ant.pos, ant.dir, pheromone))
+			Some(Trail::new(ant.pos, ant.dir, pheromone, external.now))
 		} else {
 			None
 		};
@@ -110,3 +93,238 @@ impl AntPlan for WorkerPlan {
 		Texture::Ant
 	}
 }
+
+static TRANSITIONS: TransitionLog<WorkerStateKind> = TransitionLog::new();
+
+impl StateGraph for WorkerStateKind {
+	fn states() -> Vec<Self> {
+		use strum::IntoEnumIterator;
+		Self::iter().collect()
+	}
+
+	fn name(&self) -> &'static str {
+		self.into()
+	}
+
+	fn transitions() -> Vec<(Self, Self)> {
+		let named = |name| Self::named(name).expect("Behaviour tree names an unknown state.");
+		WorkerPlan::behavior()
+			.edges()
+			.into_iter()
+			.map(|(from, to)| (named(from), named(to)))
+			.collect()
+	}
+}
+
+type WorkerSenses<'a> = Senses<'a, Worker, World>;
+type WorkerBehavior = Behavior<Worker, World, WorkerPlan>;
+type WanderReasoner = Reasoner<WanderChoice, WorkerInput>;
+
+impl WorkerPlan {
+	pub fn save_transition_graph(path: &str) -> std::io::Result<()> {
+		TRANSITIONS.save_dot(path, "Worker")
+	}
+
+	fn behavior() -> &'static WorkerBehavior {
+		static BEHAVIOR: OnceLock<WorkerBehavior> = OnceLock::new();
+		BEHAVIOR.get_or_init(|| {
+			use Behavior as B;
+
+			B::selector([
+				B::guard(
+					Self::going_home,
+					B::Task(Self::follow_home_trails).moves(&[("GoToHome", "Wander")]),
+				),
+				B::guard(
+					Self::going_to_food,
+					B::selector([
+						B::guard(Self::food_gone, B::Task(Self::give_up_on_food))
+							.moves(&[("GoToFood", "Wander")]),
+						B::guard(Self::at_food, B::Task(Self::take_food))
+							.moves(&[("GoToFood", "GoToHome")]),
+						B::Task(|_, _| Status::Running),
+					]),
+				),
+				B::guard(
+					Self::wandering,
+					B::selector([
+						B::Task(Self::choose_heading).moves(&[("Wander", "GoToFood")]),
+						B::guard(Self::reached_target, B::Task(Self::pick_target)),
+						B::Task(|_, _| Status::Running),
+					]),
+				),
+			])
+		})
+	}
+
+	//Food nearby always wins out, and trails toward food are followed once
+	//strong enough to be worth more than exploring.
+	fn wander_reasoner() -> &'static WanderReasoner {
+		static REASONER: OnceLock<WanderReasoner> = OnceLock::new();
+		REASONER.get_or_init(|| {
+			use {ResponseCurve::*, WanderChoice::*, WorkerInput::*};
+
+			Reasoner::new(Selection::Max)
+				.with(Choice::new(SeekFood, 1.).consider(
+					FoodDistance,
+					(0., Self::SMELL_RAD),
+					Inverse,
+				))
+				.with(Choice::new(FollowTrail, 0.8).consider(
+					FoodTrail,
+					(0., 2.),
+					Logistic {
+						steepness: 10.,
+						midpoint: 0.4,
+					},
+				))
+				.with(Choice::new(Explore, 0.25))
+		})
+	}
+
+	fn going_home(_: &WorkerSenses, plan: &Self) -> bool {
+		matches!(plan.state, WorkerState::GoToHome(_))
+	}
+
+	fn going_to_food(_: &WorkerSenses, plan: &Self) -> bool {
+		matches!(plan.state, WorkerState::GoToFood(_))
+	}
+
+	fn wandering(_: &WorkerSenses, plan: &Self) -> bool {
+		matches!(plan.state, WorkerState::Wander(_))
+	}
+
+	fn food_gone(senses: &WorkerSenses, plan: &Self) -> bool {
+		match plan.state {
+			WorkerState::GoToFood(food) => senses.scene.food.get(food.into()).is_none(),
+			_ => false,
+		}
+	}
+
+	fn at_food(senses: &WorkerSenses, plan: &Self) -> bool {
+		match plan.state {
+			WorkerState::GoToFood(food) => food.distance(senses.agent.pos) < Self::TRAIL_SEP,
+			_ => false,
+		}
+	}
+
+	fn reached_target(senses: &WorkerSenses, plan: &Self) -> bool {
+		match plan.state {
+			WorkerState::Wander(toward) => senses.agent.pos.distance(toward) < Self::EXPLORATION,
+			_ => false,
+		}
+	}
+
+	fn give_up_on_food(_: &WorkerSenses, plan: &mut Self) -> Status {
+		if let WorkerState::GoToFood(food) = plan.state {
+			plan.state = WorkerState::Wander(food);
+		}
+		Status::Success
+	}
+
+	fn take_food(senses: &WorkerSenses, plan: &mut Self) -> Status {
+		if let WorkerState::GoToFood(food) = plan.state {
+			let message = Dispatch::new(Some(food.into()), Signal::ConsumeFood, 0.);
+			senses
+				.messenger
+				.send(message)
+				.expect("Unable to send message.");
+			plan.state = WorkerState::GoToHome(plan.last_trail - senses.agent.pos);
+		}
+		Status::Success
+	}
+
+	//Heads for food or along trails toward it, failing if exploring is
+	//the better choice.
+	fn choose_heading(senses: &WorkerSenses, plan: &mut Self) -> Status {
+		let ant = senses.agent;
+		let food = senses.scene.food.nearest_dist(ant.pos(), Self::SMELL_RAD);
+		let trails = || {
+			senses
+				.scene
+				.trails
+				.query_at(ant.pos(), Self::TRAIL_SMELL_RAD)
+				.filter(|t| t.ty == Pheromone::ToFood)
+		};
+
+		let choice = Self::wander_reasoner().choose(|input| match input {
+			WorkerInput::FoodDistance => food.map_or(f32::INFINITY, |(d, _)| d),
+			WorkerInput::FoodTrail => trails().map(|t| t.strength(senses.scene.now)).sum(),
+		});
+
+		match (choice, food) {
+			(Some(WanderChoice::SeekFood), Some((_, food))) => {
+				plan.state = WorkerState::GoToFood(food.pos);
+				Status::Success
+			}
+			(Some(WanderChoice::FollowTrail), _) => {
+				//trails toward food are laid by ants walking away from it
+				let away = trails()
+					.map(|t| t.dir * t.strength(senses.scene.now))
+					.sum::<Vector2<f32>>();
+				if away.magnitude2() == 0. {
+					return Status::Failure;
+				}
+				let toward = ant.pos - 2. * Self::EXPLORATION * away.normalize();
+				plan.state = WorkerState::Wander(toward);
+				Status::Success
+			}
+			_ => Status::Failure,
+		}
+	}
+
+	fn pick_target(senses: &WorkerSenses, plan: &mut Self) -> Status {
+		let ant = senses.agent;
+		let offset = rand_in2d(-0.75, 0.75);
+		plan.state = WorkerState::Wander(ant.pos + 2. * Self::EXPLORATION * (ant.dir + offset));
+		Status::Success
+	}
+
+	fn follow_home_trails(senses: &WorkerSenses, plan: &mut Self) -> Status {
+		let WorkerState::GoToHome(curr) = plan.state else {
+			return Status::Failure;
+		};
+
+		let ant = senses.agent;
+		let mut sum_dir = curr * Self::PREFER_STRAIGHT;
+
+		for (d, trail) in senses
+			.scene
+			.trails
+			.query_with_dist(ant.pos.into(), Self::TRAIL_SMELL_RAD)
+			.filter(|(_, t)| ant.dir.dot(t.dir) < 0.5 && t.ty == Pheromone::ToHome)
+		{
+			let toward_trail = unit_toward(trail.pos, ant.pos);
+			let direction = 0.5 * toward_trail + trail.dir;
+			sum_dir -= direction.normalize_to(trail.strength(senses.scene.now) / (0.1 + d));
+		}
+
+		let normal = sum_dir.normalize();
+
+		plan.state = if normal != curr {
+			WorkerState::GoToHome(normal)
+		} else {
+			WorkerState::Wander(ant.pos + curr)
+		};
+
+		Status::Running
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	//Every state named in the behaviour tree exists, and every state can
+	//be both reached and left.
+	#[test]
+	fn transitions_come_from_behaviour_tree() {
+		let transitions = WorkerStateKind::transitions();
+		assert_eq!(transitions.len(), 4);
+
+		for state in WorkerStateKind::states() {
+			assert!(transitions.iter().any(|&(from, _)| from == state));
+			assert!(transitions.iter().any(|&(_, to)| to == state));
+		}
+	}
+}
@@ -4,36 +4,109 @@ use super::*;
 pub struct Food {
 	pub pos: Vector2<f32>,
 	pub amount: usize,
+	pop: Option<Tween>,
 }
 
 impl Food {
+	const LABEL_RADIUS: f32 = 3.;
+
 	pub fn new(pos: Vector2<f32>) -> Self {
-		Self { pos, amount: 10 }
+		Self {
+			pos,
+			amount: 10,
+			pop: None,
+		}
+	}
+
+	//Drawn with the rest of the food from the copy on the GPU, except while
+	//animating.
+	pub fn is_static(&self) -> bool {
+		self.pop.is_none()
+	}
+
+	//briefly swells when a piece is taken, then springs back
+	fn pop(now: std::time::Instant) -> Tween {
+		let size = |s| Property::Scale((s, s).into());
+		let swell =
+			Tween::new(now)
+				.key(0., size(1.), ease::LINEAR)
+				.key(0.08, size(1.4), ease::CUBIC_OUT);
+		let settle =
+			Tween::new(now)
+				.key(0., size(1.4), ease::LINEAR)
+				.key(0.32, size(1.), ease::ELASTIC_OUT);
+		swell.then(settle)
 	}
 }
 
 impl GameObject for Food {
 	type Scene = World;
+	//given whenever how it's drawn changes
 	type Action = ();
 
-	fn update(&mut self, _external: &External, messenger: &Messenger) -> Option<Self::Action> {
+	fn update(&mut self, external: &External, messenger: &Messenger) -> Option<Self::Action> {
+		let mut changed = None;
+
 		for _ in messenger.local_receive(self.pos(), 0., &[MessageTypes::ConsumeFood]) {
 			if self.amount > 0 {
 				self.amount -= 1;
+				self.pop = Some(Self::pop(external.now));
+				changed = Some(());
 			}
 		}
-		None
+
+		if let Some(pop) = &mut self.pop {
+			pop.poll(external.now);
+		}
+
+		if self
+			.pop
+			.as_ref()
+			.is_some_and(|pop| pop.finished(external.now))
+		{
+			self.pop = None;
+			changed = Some(());
+		}
+		changed
 	}
 
-	fn instance(&self, external: &External) -> Option<Instance> {
-		Some(
-			Instance {
-				position: self.pos.into(),
-				color_tint: (0., 0., 1., 1.).into(),
-				..external.instance(Texture::Flat)
+	fn layer(&self) -> Layer {
+		Layer::Items
+	}
+
+	fn render(&self, external: &External, out: &mut Layers) {
+		if !self.is_static() {
+			if let Some(inst) = self.instance(external) {
+				external.clip(out, self.layer(), inst);
 			}
-			.scale(self.amount as f32),
-		)
+		}
+
+		//how much is left is shown while hovered over
+		let mouse = external.camera.screen_to_world(external.mouse_pos);
+		if mouse.distance(self.pos) < Self::LABEL_RADIUS {
+			Text::new(
+				self.amount.to_string(),
+				self.pos + vec2(0., Self::LABEL_RADIUS),
+			)
+			.size(2.)
+			.color((0., 0., 0., 1.))
+			.align(Align::Centre)
+			.render(external, out, Layer::Overlay);
+		}
+	}
+
+	fn instance(&self, external: &External) -> Option<Instance> {
+		let inst = Instance {
+			position: self.pos.into(),
+			color_tint: (0., 0., 1., 1.).into(),
+			..external.instance(Texture::Flat)
+		}
+		.scale(self.amount as f32);
+
+		Some(match &self.pop {
+			Some(pop) => pop.apply(external.now, inst),
+			None => inst,
+		})
 	}
 }
 
@@ -51,4 +124,13 @@ impl utils::Relax for Food {
 	fn plan_frequency(&self) -> f32 {
 		60.
 	}
+
+	fn focus(&self) -> Option<Vector2<f32>> {
+		Some(self.pos)
+	}
+
+	//at rest unless animating a piece being taken
+	fn idle(&self) -> bool {
+		self.is_static()
+	}
 }
@@ -22,7 +22,8 @@ impl GameObject for Interface {
 				if external.point_in_view(pos) && pos.distance(last) > WorkerPlan::TRAIL_SEP =>
 			{
 				self.last_trail = Some(pos);
-				let trail = Trail::new(pos, unit_toward(pos, last), Pheromone::ToHome);
+				let trail =
+					Trail::new(pos, unit_toward(pos, last), Pheromone::ToHome, external.now);
 				Some(trail)
 			}
 			_ => None,
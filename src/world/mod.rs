@@ -20,9 +20,21 @@ use tracing::trace_span;
 pub struct World {
 	interface: Interface,
 	queen: Queen,
-	ants: Grid<Relaxed<Worker>>,
-	food: Grid<Relaxed<Food>>,
-	trails: Grid<Relaxed<Trail>>,
+	ants: MortonList<Relaxed<Worker>>,
+	food: QuadTree<Relaxed<Food>>,
+	//bumped whenever any food changes, to rebuild the static instances
+	food_version: u64,
+	//only added to each frame, and pruned by age on cleanup. Drawn on the
+	//GPU, so only kept here for smelling and heatmaps
+	trails: DenseGrid<Trail>,
+	//laid this frame, to add to those on the GPU
+	fresh_trails: Vec<GpuTrail>,
+	//as of the last update, for judging the strength of trails
+	now: std::time::Instant,
+	//times on the GPU are in seconds since
+	epoch: std::time::Instant,
+	overlay: bool,
+	trail_view: TrailView,
 }
 
 impl Root for World {
@@ -30,18 +42,35 @@ impl Root for World {
 
 	fn init() -> Self {
 		const NUM_FOOD: usize = 100;
+		const FOOD_RANGE: f32 = 1000.;
+
+		//food never moves and only spawns within a known area, so a
+		//quadtree over that area adapts to however it ends up clustered.
+		let mut food = QuadTree::new((-FOOD_RANGE, -FOOD_RANGE), (FOOD_RANGE, FOOD_RANGE));
+		food.extend(
+			std::iter::repeat_with(|| Food::new(utils::rand_in2d(-FOOD_RANGE, FOOD_RANGE)).into())
+				.take(NUM_FOOD),
+		);
 
 		Self {
 			interface: Default::default(),
 			queen: Queen::new((0., 0.).into(), 0.),
-			ants: Grid::new(200.),
-			food: Grid::from_iter(
-				32.,
-				std::iter::repeat_with(|| Food::new(utils::rand_in2d(-1000., 1000.)).into())
-					.take(NUM_FOOD),
+			//there are few ants, so keeping them sorted is cheap
+			ants: MortonList::new(200.),
+			food,
+			food_version: 0,
+			//trails are laid between the nest and the food, so a flat grid
+			//over the same area avoids hashing the many cells they fill
+			trails: DenseGrid::new(
+				(-FOOD_RANGE, -FOOD_RANGE),
+				(FOOD_RANGE, FOOD_RANGE),
+				3. * WorkerPlan::TRAIL_SEP,
 			),
-
-			trails: Grid::new(3. * WorkerPlan::TRAIL_SEP),
+			fresh_trails: vec![],
+			now: std::time::Instant::now(),
+			epoch: std::time::Instant::now(),
+			overlay: false,
+			trail_view: TrailView::Gpu,
 		}
 	}
 }
@@ -67,11 +96,33 @@ impl GameObject for World {
 		let span = trace_span!("Updating");
 		let _guard = span.enter();
 
+		self.now = external.now;
+		self.fresh_trails.clear();
+
 		{
 			let span = trace_span!("Interfacing");
 			let _guard = span.enter();
 			if let Some(trail) = self.interface.update(external, messenger) {
-				self.trails.insert(trail.into())
+				self.fresh_trails.push(trail.gpu(self.epoch));
+				self.trails.insert(trail)
+			}
+
+			if external.key(winit::event::VirtualKeyCode::H).pressed() {
+				self.save_heatmaps(external);
+			}
+
+			if external.key(winit::event::VirtualKeyCode::O).pressed() {
+				self.overlay = !self.overlay;
+			}
+
+			if external.key(winit::event::VirtualKeyCode::P).pressed() {
+				self.trail_view = self.trail_view.next();
+			}
+
+			if external.key(winit::event::VirtualKeyCode::G).pressed() {
+				if let Err(err) = WorkerPlan::save_transition_graph("graphs/worker.dot") {
+					log::error!("Unable to save transition graph: {}", err);
+				}
 			}
 		}
 
@@ -79,7 +130,9 @@ impl GameObject for World {
 			let span = trace_span!("Food");
 			let _guard = span.enter();
 			for food in self.food.iter_mut() {
-				food.update(external, messenger);
+				if food.update(external, messenger).is_some() {
+					self.food_version += 1;
+				}
 			}
 		}
 
@@ -95,38 +148,39 @@ impl GameObject for World {
 
 			for ant in self.ants.iter_mut() {
 				if let Some(trail) = ant.update(external, messenger) {
-					self.trails.insert(trail.into())
+					self.fresh_trails.push(trail.gpu(self.epoch));
+					self.trails.insert(trail)
 				}
 			}
 		}
 
-		{
-			let span = trace_span!("Trails");
-			let _guard = span.enter();
-			self.trails.par_iter_mut().for_each(|trail| {
-				trail.update(external, messenger);
-			});
-		}
-
 		rayon::in_place_scope(|s| {
 			s.spawn(|_| self.ants.maintain());
 			s.spawn(|_| self.food.maintain());
-			s.spawn(|_| self.trails.maintain());
 		});
 
 		None
 	}
 
-	fn render(&self, external: &External, out: &mut Vec<Instance>) {
+	fn render(&self, external: &External, out: &mut Layers) {
 		let span = trace_span!("Rendering");
 		let _guard = span.enter();
 
+		self.render_cached(external, out);
+
 		for food in self.food.iter() {
 			food.render(external, out);
 		}
 
-		for pher in self.trails.iter() {
-			pher.render(external, out);
+		//given even while hidden, to keep up with those laid
+		out.gpu_trails(
+			&self.fresh_trails,
+			Trail::gpu_params(external, self.epoch),
+			(self.trail_view == TrailView::Gpu).then_some(Layer::Trails),
+		);
+
+		if self.trail_view != TrailView::Gpu {
+			self.render_trail_maps(external, out);
 		}
 
 		for ant in self.ants.iter() {
@@ -134,6 +188,28 @@ impl GameObject for World {
 		}
 
 		self.queen.render(external, out);
+
+		if self.overlay {
+			self.render_overlay(external, out);
+		}
+	}
+
+	//Food at rest never moves, so is only sent to the GPU when some changes.
+	fn statics(&self) -> Option<Static> {
+		Some(Static {
+			name: "food",
+			layer: Layer::Items,
+			version: self.food_version,
+		})
+	}
+
+	fn render_static(&self, external: &External, out: &mut Vec<Instance>) {
+		out.extend(
+			self.food
+				.iter()
+				.filter(|food| food.is_static())
+				.filter_map(|food| food.instance(external)),
+		);
 	}
 
 	fn cleanup(&mut self) {
@@ -142,9 +218,162 @@ impl GameObject for World {
 
 		self.ants.cleanup();
 		self.food.cleanup();
+		//trails faded since the last cleanup are left until now, when they
+		//are already too faint to count for much
+		let now = self.now;
+		self.trails.retain(|trail| trail.alive_at(now));
 		self.trails.cleanup();
 
+		//most trails fade together once a food source runs out, leaving
+		//the memory they held behind
+		if self.trails.sparse() {
+			self.trails.compact();
+		}
+
 		//self.ants.dbg_analytics();
 		//self.trails.dbg_analytics();
 	}
 }
+
+impl World {
+	//cells holding this many or more are drawn fully opaque
+	const OVERLAY_FULL: usize = 32;
+	//how near the cursor an ant must be to show what it can sense
+	const OVERLAY_PICK: f32 = 20.;
+
+	//Outlines the cells of each container in its own colour, filled more
+	//strongly the more they hold, and circles what the ant nearest the
+	//cursor can smell, to help tune the cell sizes.
+	fn render_overlay(&self, external: &External, out: &mut Layers) {
+		let cells = [
+			(self.ants.cells(), (0., 0., 1.)),
+			(self.food.cells(), (0., 0.6, 0.)),
+			(self.trails.cells(), (1., 0., 0.)),
+		];
+
+		let mut shapes = Shapes::new(external, out, Layer::Overlay);
+		let mut labels = vec![];
+
+		for (cells, (r, g, b)) in cells {
+			for cell in cells {
+				let lo = Vector2::from(cell.lo);
+				let hi = Vector2::from(cell.hi);
+				let (centre, size) = ((lo + hi) / 2., hi - lo);
+
+				let fill = 0.4 * (cell.count as f32 / Self::OVERLAY_FULL as f32).min(1.);
+				shapes
+					.color((r, g, b, fill))
+					.rect(centre, size)
+					.color((r, g, b, 1.))
+					.thickness(size.x / 100.)
+					.rect_outline(centre, size);
+
+				if cell.count > 0 {
+					labels.push(
+						Text::new(cell.count.to_string(), centre)
+							.size(size.y / 8.)
+							.color((r, g, b, 1.))
+							.align(Align::Centre),
+					);
+				}
+			}
+		}
+
+		let mouse = external.camera.screen_to_world(external.mouse_pos);
+		if let Some(ant) = self.ants.nearest(mouse.into(), Self::OVERLAY_PICK) {
+			shapes
+				.thickness(1.)
+				.color((0., 0.6, 0., 1.))
+				.circle_outline(ant.pos, WorkerPlan::SMELL_RAD)
+				.color((1., 0., 0., 1.))
+				.circle_outline(ant.pos, WorkerPlan::TRAIL_SMELL_RAD)
+				.color((0., 0., 0., 1.))
+				.arrow(ant.pos, ant.pos + ant.dir.normalize_to(10.));
+		}
+
+		for label in labels {
+			label.render(external, out, Layer::Overlay);
+		}
+	}
+
+	//rows of pixels in the trail heatmaps covering the view
+	const TRAIL_MAP_RES: u32 = 96;
+	const TRAIL_MAP_BLUR: u32 = 1;
+
+	//Splats the trails in view into a heatmap per pheromone, each stretched
+	//over the whole view.
+	fn render_trail_maps(&self, external: &External, out: &mut Layers) {
+		let view = external.view_dims();
+		let lo = (external.camera.pos - view / 2.).into();
+		let hi = (external.camera.pos + view / 2.).into();
+		let res = (
+			(Self::TRAIL_MAP_RES as f32 * external.aspect()) as u32,
+			Self::TRAIL_MAP_RES,
+		);
+
+		for ty in Pheromone::ALL {
+			let Some(ramp) = self.trail_view.ramp(ty) else {
+				continue;
+			};
+
+			let density = self
+				.trails
+				.weighted_density(lo, hi, res, |t| {
+					if t.ty == ty {
+						t.strength(external.now)
+					} else {
+						0.
+					}
+				})
+				.blurred(Self::TRAIL_MAP_BLUR);
+
+			out.heatmap(
+				Layer::Trails,
+				density.heatmap(ramp),
+				Instance {
+					position: external.camera.pos.into(),
+					scale: view.into(),
+					..Default::default()
+				},
+			);
+		}
+	}
+
+	const HEATMAP_DIR: &'static str = "heatmaps";
+	const HEATMAP_RES: u32 = 256;
+
+	//Writes density images of the area in view, named by the time at
+	//which they were taken, so that repeated snapshots can be compared.
+	fn save_heatmaps(&self, external: &External) {
+		let half = external.view_dims() / 2.;
+		let lo = (external.camera.pos - half).into();
+		let hi = (external.camera.pos + half).into();
+		let res = (
+			(Self::HEATMAP_RES as f32 * external.aspect()) as u32,
+			Self::HEATMAP_RES,
+		);
+
+		let ants = self.ants.density(lo, hi, res);
+		let trails = self
+			.trails
+			.weighted_density(lo, hi, res, |t| t.strength(external.now));
+		let food = self.food.weighted_density(lo, hi, res, |f| f.amount as f32);
+
+		let stamp = std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.map_or(0, |d| d.as_millis());
+
+		let saved = std::fs::create_dir_all(Self::HEATMAP_DIR)
+			.map_err(image::ImageError::from)
+			.and_then(|_| {
+				for (name, density) in [("ants", ants), ("trails", trails), ("food", food)] {
+					density.save_png(format!("{}/{}_{}.png", Self::HEATMAP_DIR, name, stamp))?;
+				}
+				Ok(())
+			});
+
+		if let Err(err) = saved {
+			log::error!("Unable to save heatmaps: {}", err);
+		}
+	}
+}
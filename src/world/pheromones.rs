@@ -1,11 +1,13 @@
 use super::*;
 use cgmath::*;
+use std::time::Instant;
 
 pub struct Trail {
 	pub pos: Vector2<f32>,
 	pub dir: Vector2<f32>,
 	pub ty: Pheromone,
-	pub strength: f32,
+	//strength only depends on age, so trails never need updating
+	pub born: Instant,
 }
 
 #[repr(u32)]
@@ -15,66 +17,148 @@ pub enum Pheromone {
 	ToHome,
 }
 
+impl Pheromone {
+	pub const ALL: [Pheromone; 2] = [Pheromone::ToFood, Pheromone::ToHome];
+}
+
+//How trails are drawn: faded and placed on the GPU, or a heatmap per
+//pheromone with one of a few colour ramps, which reads better once there
+//are many of them.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum TrailView {
+	Gpu,
+	Tinted,
+	Heat,
+}
+
+impl TrailView {
+	pub fn next(self) -> Self {
+		match self {
+			TrailView::Gpu => TrailView::Tinted,
+			TrailView::Tinted => TrailView::Heat,
+			TrailView::Heat => TrailView::Gpu,
+		}
+	}
+
+	//Colours the heatmap of a pheromone, or None if drawn on the GPU.
+	pub fn ramp(self, ty: Pheromone) -> Option<Ramp> {
+		Some(match (self, ty) {
+			(TrailView::Gpu, _) => return None,
+			(TrailView::Tinted, Pheromone::ToFood) => Ramp::new(Density::tint((0, 255, 0))),
+			(TrailView::Tinted, Pheromone::ToHome) => Ramp::new(Density::tint((255, 0, 0))),
+			//black -> green -> yellow -> white, to tell it apart from the other
+			(TrailView::Heat, Pheromone::ToFood) => Ramp::new(Density::fading(
+				|f| {
+					let [r, g, b, a] = Density::HEAT(f).0;
+					image::Rgba([g, r, b, a])
+				},
+				0.25,
+			)),
+			(TrailView::Heat, Pheromone::ToHome) => Ramp::new(Density::fading(Density::HEAT, 0.25)),
+		})
+	}
+}
+
 impl Trail {
 	pub const SIZE: f32 = 3.;
 	pub const HALF_LIFE: f32 = 7.0;
 	pub const ALIVE_THRESHOLD: f32 = 0.0125;
 	pub const DECAY_RATE: f32 = -std::f32::consts::LN_2 / Self::HALF_LIFE;
 
-	pub fn new(pos: Vector2<f32>, dir: Vector2<f32>, ty: Pheromone) -> Self {
+	pub fn new(pos: Vector2<f32>, dir: Vector2<f32>, ty: Pheromone, now: Instant) -> Self {
 		Self {
 			pos,
 			ty,
 			dir,
-			strength: 1.0,
+			born: now,
 		}
 	}
-}
 
-impl GameObject for Trail {
-	type Scene = World;
-	type Action = ();
+	//Integrates the decay, starting from full strength.
+	pub fn strength(&self, now: Instant) -> f32 {
+		f32::exp(Self::DECAY_RATE * self.age(now))
+	}
 
-	fn update(&mut self, external: &External, _messenger: &Messenger) -> Option<Self::Action> {
-		//Integrates to
-		self.strength += external.delta * Self::DECAY_RATE * self.strength;
-		None
+	pub fn age(&self, now: Instant) -> f32 {
+		now.saturating_duration_since(self.born).as_secs_f32()
 	}
 
-	fn instance(&self, external: &External) -> Option<Instance> {
-		let color = match self.ty {
-			Pheromone::ToFood => (0., 1., 0., self.strength),
-			Pheromone::ToHome => (1., 0., 0., self.strength),
-		};
-
-		//Some math shows this is the time elapsed
-		let elapsed = f32::ln(self.strength) / Self::DECAY_RATE;
-
-		Some(
-			Instance {
-				position: self.pos.into(),
-				rotation: (90. * elapsed).into(),
-				color_tint: color.into(),
-				scale: (2, 2).into(),
-				..external.instance(Texture::Swirl)
-			}
-			.scale(Self::SIZE),
-		)
+	//seconds until too faint to be alive
+	pub fn lifetime() -> f32 {
+		f32::ln(Self::ALIVE_THRESHOLD) / Self::DECAY_RATE
 	}
-}
 
-impl utils::Relax for Trail {
-	fn plan_frequency(&self) -> f32 {
-		60.
+	pub fn alive_at(&self, now: Instant) -> bool {
+		self.age(now) < Self::lifetime()
+	}
+
+	//Times on the GPU are in seconds since `epoch`, which must be the same
+	//as given to Trail::gpu_params.
+	pub fn gpu(&self, epoch: Instant) -> GpuTrail {
+		GpuTrail {
+			position: self.pos.into(),
+			born: self
+				.born
+				.saturating_duration_since(epoch)
+				.as_secs_f32()
+				.into(),
+			kind: GLuint(self.ty as u32),
+		}
+	}
+
+	//Fades trails on the GPU from their birth as Trail::strength does, and
+	//draws them as swirls.
+	pub fn gpu_params(external: &External, epoch: Instant) -> TrailParams {
+		let inst = Instance {
+			scale: (2, 2).into(),
+			..external.instance(Texture::Swirl)
+		}
+		.scale(Self::SIZE);
+
+		TrailParams {
+			texture: inst.texture,
+			scale: inst.scale,
+			decay_rate: Self::DECAY_RATE.into(),
+			now: external
+				.now
+				.saturating_duration_since(epoch)
+				.as_secs_f32()
+				.into(),
+			threshold: Self::ALIVE_THRESHOLD.into(),
+			atlas: inst.atlas,
+		}
 	}
 }
 
+//Trails are removed once faded by the world on cleanup, which knows the
+//time.
 impl Griddable for Trail {
 	fn pos(&self) -> (f32, f32) {
 		self.pos.into()
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	//Only the times given matter, not how long the test takes.
+	#[test]
+	fn fades_by_the_time_given() {
+		let epoch = Instant::now();
+		let born = epoch + Duration::from_secs(5);
+		let trail = Trail::new(vec2(1., 2.), vec2(1., 0.), Pheromone::ToHome, born);
+
+		let half_life = born + Duration::from_secs_f32(Trail::HALF_LIFE);
+		assert!((trail.strength(half_life) - 0.5).abs() < 1e-4);
+
+		let lifetime = Duration::from_secs_f32(Trail::lifetime());
+		assert!(trail.alive_at(born + lifetime - Duration::from_millis(10)));
+		assert!(!trail.alive_at(born + lifetime + Duration::from_millis(10)));
 
-	fn alive(&self) -> bool {
-		self.strength > Self::ALIVE_THRESHOLD
+		let gpu = trail.gpu(epoch);
+		assert_eq!(gpu.born.0, 5.);
+		assert_eq!(gpu.kind.0, Pheromone::ToHome as u32);
 	}
 }
@@ -3,6 +3,8 @@ use strum_macros::{EnumIter, IntoStaticStr};
 
 use crate::window::TextureType;
 
+//Textures the game refers to directly. Everything else listed in the
+//asset manifest is loaded too, and can be referred to by name.
 #[derive(IntoStaticStr, EnumIter, Hash, PartialEq, Debug, Eq, Clone, Copy)]
 pub enum Texture {
 	Ant,
@@ -20,8 +22,4 @@ impl TextureType for Texture {
 	fn name(&self) -> &'static str {
 		self.into()
 	}
-
-	fn frame_count(&self) -> u32 {
-		1
-	}
 }
@@ -0,0 +1,54 @@
+use ants::window::*;
+
+const SIZE: (u32, u32) = (64, 48);
+
+//Opaque quad over the pixels [x0, x1) x [y0, y1), from the top left.
+fn quad(x0: u32, x1: u32, y0: u32, y1: u32, color: GLvec4) -> Instance {
+	let (w, h) = (SIZE.0 as f32, SIZE.1 as f32);
+	let (x0, x1, y0, y1) = (x0 as f32, x1 as f32, y0 as f32, y1 as f32);
+
+	Instance {
+		color_tint: color,
+		scale: GLvec2(2. * (x1 - x0) / w, 2. * (y1 - y0) / h),
+		position: GLvec2((x0 + x1) / w - 1., 1. - (y0 + y1) / h),
+		screen_relative: GLbool::True,
+		..Default::default()
+	}
+}
+
+#[test]
+fn quads_match_golden() {
+	let white = image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4]));
+	let Some(mut offscreen) = Offscreen::new(SIZE, &[white], true) else {
+		//only skipped where a GPU can't be expected, so that CI can't pass
+		//without actually rendering
+		let required = ["CI", "REQUIRE_GPU"]
+			.iter()
+			.any(|var| std::env::var_os(var).is_some());
+		assert!(!required, "no suitable graphics adapter");
+		eprintln!("no graphics adapter, skipping");
+		return;
+	};
+
+	let mut layers = Layers::default();
+	//later layers cover earlier ones whatever the order they are given in
+	layers.push(Layer::Ui, quad(40, 60, 28, 44, GLvec4(0., 0., 1., 1.)));
+	//and within a layer, later instances cover earlier ones
+	layers.push(Layer::Items, quad(4, 28, 4, 20, GLvec4(0., 0., 0., 1.)));
+	layers.push(Layer::Items, quad(16, 48, 12, 36, GLvec4(1., 0., 0., 1.)));
+
+	let camera = Camera {
+		pos: cgmath::vec2(0., 0.),
+		scale: 1.,
+	};
+	let rendered = offscreen.render(&camera, &layers);
+
+	let golden = image::open(concat!(
+		env!("CARGO_MANIFEST_DIR"),
+		"/tests/golden/quads.png"
+	))
+	.unwrap()
+	.to_rgba8();
+
+	assert_eq!(difference(&rendered, &golden, 2), Some(0.));
+}